@@ -10,6 +10,7 @@ use num_traits::{One, Zero};
 use crate::{cross::CrossProduct, dot::Dot, Scalar, Tensor};
 
 #[derive(Default, Clone, Copy, Eq, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3<T> {
     pub x: T,
     pub y: T,
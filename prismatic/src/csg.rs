@@ -0,0 +1,215 @@
+use std::vec::IntoIter;
+
+use anyhow::anyhow;
+use math::{BaseOrigin, Scalar};
+
+use crate::{
+    geometry::GeometryDyn,
+    indexes::geo_index::{
+        boolean::BooleanOp,
+        geo_object::GeoObject,
+        index::GeoIndex,
+        mesh::MeshId,
+    },
+};
+
+/// Tree of boolean operations over geometries.
+///
+/// All leaves are inserted into index before any boolean operation is made, so intersections
+/// between all of them are calculated only once. After that, tree is folded bottom-up into single
+/// mesh.
+pub enum Csg<S: Scalar> {
+    Leaf {
+        geometry: Box<dyn GeometryDyn<S>>,
+        origin: BaseOrigin<S>,
+    },
+    Union(Vec<Csg<S>>),
+    Intersection(Vec<Csg<S>>),
+    Difference(Box<Csg<S>>, Vec<Csg<S>>),
+}
+
+impl<S: Scalar> Csg<S> {
+    pub fn leaf(geometry: impl GeometryDyn<S> + 'static) -> Self {
+        Self::Leaf {
+            geometry: Box::new(geometry),
+            origin: BaseOrigin::new(),
+        }
+    }
+
+    pub fn leaf_with_origin(geometry: impl GeometryDyn<S> + 'static, origin: BaseOrigin<S>) -> Self {
+        Self::Leaf {
+            geometry: Box::new(geometry),
+            origin,
+        }
+    }
+
+    pub fn union(items: impl IntoIterator<Item = Csg<S>>) -> Self {
+        Self::Union(items.into_iter().collect())
+    }
+
+    pub fn intersection(items: impl IntoIterator<Item = Csg<S>>) -> Self {
+        Self::Intersection(items.into_iter().collect())
+    }
+
+    pub fn difference(from: Csg<S>, items: impl IntoIterator<Item = Csg<S>>) -> Self {
+        Self::Difference(Box::new(from), items.into_iter().collect())
+    }
+
+    /// Evaluates tree into single mesh of the index.
+    pub fn evaluate(&self, index: &mut GeoIndex<S>) -> anyhow::Result<MeshId> {
+        let mut leaves = Vec::new();
+        self.insert_leaves(index, &mut leaves)?;
+        let mut leaves = leaves.into_iter();
+        self.fold(index, &mut leaves)
+    }
+
+    fn insert_leaves(&self, index: &mut GeoIndex<S>, leaves: &mut Vec<MeshId>) -> anyhow::Result<()> {
+        match self {
+            Csg::Leaf { geometry, origin } => {
+                let mesh_id = index.new_mesh();
+                geometry.polygonize_with_origin(mesh_id.make_mut_ref(index), origin.clone())?;
+                leaves.push(mesh_id);
+            }
+            Csg::Union(items) | Csg::Intersection(items) => {
+                for item in items {
+                    item.insert_leaves(index, leaves)?;
+                }
+            }
+            Csg::Difference(from, items) => {
+                from.insert_leaves(index, leaves)?;
+                for item in items {
+                    item.insert_leaves(index, leaves)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Leaves are consumed in the same order, as they were inserted by `insert_leaves`.
+    fn fold(&self, index: &mut GeoIndex<S>, leaves: &mut IntoIter<MeshId>) -> anyhow::Result<MeshId> {
+        match self {
+            Csg::Leaf { .. } => leaves
+                .next()
+                .ok_or_else(|| anyhow!("Leaf mesh is missing")),
+            Csg::Union(items) => Self::fold_items(index, leaves, items, BooleanOp::Union),
+            Csg::Intersection(items) => {
+                Self::fold_items(index, leaves, items, BooleanOp::Intersection)
+            }
+            Csg::Difference(from, items) => {
+                let mut result = from.fold(index, leaves)?;
+                for item in items {
+                    let other = item.fold(index, leaves)?;
+                    result = index.boolean(result, other, BooleanOp::Difference)?;
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    fn fold_items(
+        index: &mut GeoIndex<S>,
+        leaves: &mut IntoIter<MeshId>,
+        items: &[Csg<S>],
+        op: BooleanOp,
+    ) -> anyhow::Result<MeshId> {
+        let (first, rest) = items
+            .split_first()
+            .ok_or_else(|| anyhow!("Cannot evaluate {op:?} without operands"))?;
+        let mut result = first.fold(index, leaves)?;
+        for item in rest {
+            let other = item.fold(index, leaves)?;
+            result = index.boolean(result, other, op)?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::{BaseOrigin, Vector3};
+
+    use crate::{
+        geometry::GeometryDyn,
+        indexes::{
+            aabb::Aabb,
+            geo_index::{geo_object::GeoObject, index::GeoIndex},
+        },
+    };
+
+    use super::Csg;
+
+    #[derive(Clone)]
+    struct Cube {
+        center: Vector3<f64>,
+        size: f64,
+    }
+
+    impl GeometryDyn<f64> for Cube {
+        fn render(&self) -> Vec<Vec<Vector3<f64>>> {
+            self.render_with_origin(BaseOrigin::new())
+        }
+
+        fn render_with_origin(&self, basis: BaseOrigin<f64>) -> Vec<Vec<Vector3<f64>>> {
+            let h = self.size / 2.0;
+            let v = |x: f64, y: f64, z: f64| {
+                basis.rotation * (self.center + Vector3::new(x * h, y * h, z * h)) + basis.center
+            };
+            vec![
+                vec![v(-1., -1., -1.), v(-1., 1., -1.), v(1., 1., -1.), v(1., -1., -1.)],
+                vec![v(-1., -1., 1.), v(1., -1., 1.), v(1., 1., 1.), v(-1., 1., 1.)],
+                vec![v(-1., -1., -1.), v(1., -1., -1.), v(1., -1., 1.), v(-1., -1., 1.)],
+                vec![v(-1., 1., -1.), v(-1., 1., 1.), v(1., 1., 1.), v(1., 1., -1.)],
+                vec![v(-1., -1., -1.), v(-1., -1., 1.), v(-1., 1., 1.), v(-1., 1., -1.)],
+                vec![v(1., -1., -1.), v(1., 1., -1.), v(1., 1., 1.), v(1., -1., 1.)],
+            ]
+        }
+    }
+
+    fn cube(x: f64, size: f64) -> Csg<f64> {
+        Csg::leaf(Cube {
+            center: Vector3::new(x, 0.0, 0.0),
+            size,
+        })
+    }
+
+    fn evaluate(csg: Csg<f64>) -> f64 {
+        let mut index = GeoIndex::new(Aabb::from_points(&[
+            Vector3::new(-10.0, -10.0, -10.0),
+            Vector3::new(10.0, 10.0, 10.0),
+        ]))
+        .points_precision(0.001);
+        let mesh_id = csg.evaluate(&mut index).unwrap();
+        let mesh = mesh_id.make_ref(&index);
+        assert!(mesh.validate().is_valid());
+        mesh.volume()
+    }
+
+    #[test]
+    fn union_of_several_items() {
+        let volume = evaluate(Csg::union([cube(0.0, 2.0), cube(1.0, 2.0), cube(2.0, 2.0)]));
+        assert!((volume - 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn intersection_of_several_items() {
+        let volume = evaluate(Csg::intersection([
+            cube(0.0, 2.0),
+            cube(0.5, 2.0),
+            cube(1.0, 2.0),
+        ]));
+        assert!((volume - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn difference_makes_cavity() {
+        let volume = evaluate(Csg::difference(cube(0.0, 4.0), [cube(0.0, 1.0)]));
+        assert!((volume - 63.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nested_difference() {
+        let hollow = Csg::difference(cube(0.0, 2.0), [cube(0.0, 1.0)]);
+        let volume = evaluate(Csg::difference(cube(0.0, 4.0), [hollow]));
+        assert!((volume - 57.0).abs() < 1e-9);
+    }
+}
@@ -13,6 +13,7 @@ use super::{
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Aabb<S> {
     pub(crate) min: Vector3<S>,
     pub(crate) max: Vector3<S>,
@@ -42,6 +43,14 @@ impl<S> Aabb<S>
 where
     S: Scalar,
 {
+    pub fn min(&self) -> Vector3<S> {
+        self.min
+    }
+
+    pub fn max(&self) -> Vector3<S> {
+        self.max
+    }
+
     pub fn split_x(&self) -> [Self; 2] {
         let middle = self.min.lerp(&self.max, S::half());
 
@@ -1,8 +1,23 @@
+pub mod boolean;
+pub mod compact;
+pub mod containment;
+pub mod distance;
 pub mod face;
 pub mod geo_object;
 pub mod index;
+pub mod junction;
+pub mod merge;
 pub mod mesh;
+pub mod orientation;
 pub mod poly;
 pub mod poly_rtree;
+pub mod raycast;
 pub mod rib;
 pub mod seg;
+pub mod slice;
+pub mod split;
+pub mod transaction;
+pub mod transform;
+pub mod validation;
+#[cfg(feature = "serde")]
+mod snapshot;
@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use math::Scalar;
+
+use super::{
+    containment::Containment,
+    face::FaceId,
+    geo_object::GeoObject,
+    index::{GeoIndex, PolygonFilter},
+    mesh::MeshId,
+    poly::PolyId,
+    rib::RibId,
+    seg::SegmentDir,
+    transaction::MapMut,
+};
+
+/// Boolean operation between two meshes of the same index.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum BooleanOp {
+    Union,
+    Difference,
+    Intersection,
+}
+
+impl BooleanOp {
+    /// Decides, if polygon of the first operand stays in result.
+    /// `same_dir` matters only for shared polygons - it tells, if other mesh uses same face with
+    /// same direction.
+    fn keep_first(&self, mark: PolygonFilter, same_dir: bool) -> bool {
+        match (self, mark) {
+            (BooleanOp::Intersection, PolygonFilter::Front) => false,
+            (_, PolygonFilter::Front) => true,
+            (BooleanOp::Intersection, PolygonFilter::Back) => true,
+            (_, PolygonFilter::Back) => false,
+            (BooleanOp::Difference, PolygonFilter::Shared) => !same_dir,
+            (_, PolygonFilter::Shared) => same_dir,
+        }
+    }
+
+    /// Decides, if polygon of the second operand stays in result.
+    /// Shared polygons are never taken from second operand: if they are needed - they are already
+    /// taken from first one.
+    fn keep_second(&self, mark: PolygonFilter) -> bool {
+        match (self, mark) {
+            (BooleanOp::Union, PolygonFilter::Front) => true,
+            (_, PolygonFilter::Front) => false,
+            (BooleanOp::Union, PolygonFilter::Back) => false,
+            (_, PolygonFilter::Back) => true,
+            (_, PolygonFilter::Shared) => false,
+        }
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Union of two meshes. Both meshes are consumed, and their polygons, which form the result,
+    /// are moved to new mesh.
+    pub fn union(&mut self, a: MeshId, b: MeshId) -> anyhow::Result<MeshId> {
+        self.boolean(a, b, BooleanOp::Union)
+    }
+
+    /// Subtracts mesh `b` from mesh `a`. Both meshes are consumed, polygons of `b` which become
+    /// part of result are flipped.
+    pub fn difference(&mut self, a: MeshId, b: MeshId) -> anyhow::Result<MeshId> {
+        self.boolean(a, b, BooleanOp::Difference)
+    }
+
+    /// Intersection of two meshes. Both meshes are consumed.
+    pub fn intersection(&mut self, a: MeshId, b: MeshId) -> anyhow::Result<MeshId> {
+        self.boolean(a, b, BooleanOp::Intersection)
+    }
+
+    /// Polygons, which are not connected to intersection of meshes, are not classified by
+    /// `select_polygons`. Each part of mesh, made of such polygons, is checked against other mesh
+    /// with a point of one of its polygons.
+    pub(crate) fn boolean(
+        &mut self,
+        a: MeshId,
+        b: MeshId,
+        op: BooleanOp,
+    ) -> anyhow::Result<MeshId> {
+        if a == b {
+            return Err(anyhow!("Cannot make boolean of mesh {a:?} with itself"));
+        }
+        for mesh_id in [a, b] {
+            if !self.meshes.contains_key(&mesh_id) {
+                return Err(anyhow!("Mesh id {mesh_id:?} not found"));
+            }
+        }
+
+        let mut a_marks = self.classify_polygons(a, b);
+        let mut b_marks = self.classify_polygons(b, a);
+        self.mark_detached_parts(a, b, &mut a_marks);
+        self.mark_detached_parts(b, a, &mut b_marks);
+        let b_dirs: HashMap<FaceId, SegmentDir> = self.meshes[&b]
+            .polies
+            .values()
+            .map(|poly| (poly.face_id, poly.dir))
+            .collect();
+
+        let keep_a = self.meshes[&a]
+            .polies
+            .iter()
+            .filter(|(poly_id, poly)| {
+                let mark = a_marks[poly_id];
+                let same_dir = b_dirs
+                    .get(&poly.face_id)
+                    .is_some_and(|dir| *dir == poly.dir);
+                op.keep_first(mark, same_dir)
+            })
+            .map(|(poly_id, _)| *poly_id)
+            .collect_vec();
+
+        let keep_b = self.meshes[&b]
+            .polies
+            .keys()
+            .filter(|poly_id| {
+                let mark = b_marks[poly_id];
+                op.keep_second(mark)
+            })
+            .copied()
+            .collect_vec();
+
+        let result = self.new_mesh();
+        self.move_kept_polygons(a, keep_a, result, false);
+        self.move_kept_polygons(b, keep_b, result, op == BooleanOp::Difference);
+
+        Ok(result)
+    }
+
+    /// Marks polygons of `of_mesh`, which `classify_polygons` left unmarked. They make parts of
+    /// mesh, which do not touch `by_mesh`, so each part is wholly inside of it or wholly outside.
+    /// Part is tested with a point inside of its polygon; polygon, lying on `by_mesh`, cannot
+    /// tell it, and next one is taken. Part, lying on `by_mesh` entirely, is taken as outside.
+    fn mark_detached_parts(
+        &self,
+        of_mesh: MeshId,
+        by_mesh: MeshId,
+        marks: &mut BTreeMap<PolyId, PolygonFilter>,
+    ) {
+        let mut left = self.meshes[&of_mesh]
+            .polies
+            .keys()
+            .filter(|poly_id| !marks.contains_key(poly_id))
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let poly_ribs = |poly_id: PolyId| {
+            self.load_polygon_ref(of_mesh, poly_id)
+                .segments()
+                .map(|s| s.rib_id())
+                .collect_vec()
+        };
+        let mut rib_polies: HashMap<RibId, Vec<PolyId>> = HashMap::new();
+        for poly_id in &left {
+            for rib_id in poly_ribs(*poly_id) {
+                rib_polies.entry(rib_id).or_default().push(*poly_id);
+            }
+        }
+
+        let by_mesh = by_mesh.make_ref(self);
+        while let Some(first) = left.pop_first() {
+            let mut part = vec![first];
+            let mut queue = VecDeque::from([first]);
+            while let Some(poly_id) = queue.pop_front() {
+                for rib_id in poly_ribs(poly_id) {
+                    for next in &rib_polies[&rib_id] {
+                        if left.remove(next) {
+                            part.push(*next);
+                            queue.push_back(*next);
+                        }
+                    }
+                }
+            }
+
+            let mark = part
+                .iter()
+                .find_map(|poly_id| {
+                    let face_id = self.meshes[&of_mesh].polies[poly_id].face_id;
+                    let [a, b, c] = *face_id.make_ref(self).triangulate().ok()?.first()?;
+                    match by_mesh.contains_point((a + b + c) / S::from_value(3.0)) {
+                        Containment::Inside => Some(PolygonFilter::Back),
+                        Containment::Outside => Some(PolygonFilter::Front),
+                        Containment::OnBoundary => None,
+                    }
+                })
+                .unwrap_or(PolygonFilter::Front);
+            for poly_id in part {
+                marks.insert(poly_id, mark);
+            }
+        }
+    }
+
+    /// Moves selected polygons of the mesh to other mesh, and removes source mesh together with
+    /// rest of its polygons.
+    fn move_kept_polygons(&mut self, from: MeshId, keep: Vec<PolyId>, to: MeshId, flip: bool) {
+        for poly_id in keep {
+            if let Some(mut poly) = self
+                .meshes
+                .get_mut(&from)
+                .and_then(|m| m.polies.remove(&poly_id))
+            {
+                if flip {
+                    poly.dir = poly.dir.flip();
+                }
+                if let Some(mesh) = self.meshes.get_mut(&to) {
+                    mesh.add(poly);
+                }
+            }
+        }
+
+        let left = self.meshes[&from].polies.keys().copied().collect_vec();
+        for poly_id in left {
+            self.remove_polygon(poly_id, from);
+        }
+        self.meshes.remove(&from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        index::GeoIndex,
+        mesh::tests::{cube, new_index},
+    };
+
+    /// Volumes of union, difference and intersection of two cubes, each made in its own index.
+    fn boolean_volumes(a: (Vector3<f64>, f64), b: (Vector3<f64>, f64)) -> [f64; 3] {
+        [
+            GeoIndex::union,
+            GeoIndex::difference,
+            GeoIndex::intersection,
+        ]
+        .map(|op| {
+            let mut index = new_index();
+            let a = cube(&mut index, a.0, a.1);
+            let b = cube(&mut index, b.0, b.1);
+            let result = op(&mut index, a, b).unwrap();
+            let mesh = result.make_ref(&index);
+            assert!(mesh.all_polygons().is_empty() || mesh.validate().is_valid());
+            mesh.volume()
+        })
+    }
+
+    fn assert_volumes(volumes: [f64; 3], expected: [f64; 3]) {
+        for (volume, expected) in volumes.into_iter().zip(expected) {
+            assert!(
+                (volume - expected).abs() < 1e-9,
+                "{volumes:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn boolean_of_nested_meshes() {
+        let outer = (Vector3::new(0.0, 0.0, 0.0), 4.0);
+        let inner = (Vector3::new(0.5, 0.5, 0.5), 1.0);
+        assert_volumes(boolean_volumes(outer, inner), [64.0, 63.0, 1.0]);
+        assert_volumes(boolean_volumes(inner, outer), [64.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn boolean_of_disjoint_meshes() {
+        let a = (Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = (Vector3::new(4.0, 0.0, 0.0), 1.0);
+        assert_volumes(boolean_volumes(a, b), [9.0, 8.0, 0.0]);
+    }
+
+    #[test]
+    fn boolean_of_meshes_with_shared_face() {
+        let a = (Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = (Vector3::new(2.0, 0.0, 0.0), 2.0);
+        assert_volumes(boolean_volumes(a, b), [16.0, 8.0, 0.0]);
+    }
+
+    #[test]
+    fn boolean_of_overlapping_meshes() {
+        let a = (Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = (Vector3::new(1.0, 1.0, 1.0), 2.0);
+        assert_volumes(boolean_volumes(a, b), [15.0, 7.0, 1.0]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use math::Scalar;
+use rstar::RTree;
+
+use crate::indexes::vertex_index::{PtId, VertexIndex};
+
+use super::{
+    face::FaceId,
+    index::GeoIndex,
+    poly_rtree::FaceRtreeRecord,
+    rib::{Rib, RibId},
+};
+
+/// New ids of faces and points, which are left after `GeoIndex::compact`. Dropped objects are
+/// missing here.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactMap {
+    pub faces: BTreeMap<FaceId, FaceId>,
+    pub points: BTreeMap<PtId, PtId>,
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Drops faces, which are not used by any mesh, deleted faces and history of splits; then
+    /// drops ribs and points, which are not used by faces left. Objects left get new ids in the
+    /// same order, as old ones had, and spatial indexes are rebuilt.
+    /// Returns new ids for old ones, so that ids, kept outside of index, can be updated.
+    /// Fails, when transaction is open: rollback could not bring old ids back.
+    pub fn compact(&mut self) -> anyhow::Result<CompactMap> {
+        if self.in_transaction() {
+            return Err(anyhow!("Index cannot be compacted in transaction"));
+        }
+        let faces: BTreeMap<FaceId, FaceId> = self
+            .meshes
+            .values()
+            .flat_map(|mesh| mesh.polies.values().map(|poly| poly.face_id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(ix, face_id)| (face_id, FaceId(ix + 1)))
+            .collect();
+        let ribs: BTreeMap<RibId, RibId> = faces
+            .keys()
+            .flat_map(|face_id| self.faces[face_id].ribs.iter().copied())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(ix, rib_id)| (rib_id, RibId(ix + 1)))
+            .collect();
+        // Points keep their order, so first point of each rib stays the smaller one.
+        let points: BTreeMap<PtId, PtId> = ribs
+            .keys()
+            .flat_map(|rib_id| [self.ribs[rib_id].0, self.ribs[rib_id].1])
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(ix, pt)| (pt, PtId::from(ix)))
+            .collect();
+
+        self.vertices = VertexIndex::from_points(
+            self.vertices.aabb(),
+            points
+                .keys()
+                .map(|pt| self.vertices.get_point(*pt))
+                .collect(),
+        );
+        self.ribs = ribs
+            .iter()
+            .map(|(old, new)| {
+                let rib = self.ribs[old];
+                (*new, Rib(points[&rib.0], points[&rib.1]))
+            })
+            .collect();
+        self.faces = faces
+            .iter()
+            .map(|(old, new)| (*new, self.faces[old].with_renamed_ribs(&ribs)))
+            .collect();
+        let mut meshes = std::mem::take(&mut self.meshes).into_inner();
+        for poly in meshes
+            .values_mut()
+            .flat_map(|mesh| mesh.polies.values_mut())
+        {
+            poly.face_id = faces[&poly.face_id];
+        }
+        self.meshes = meshes.into();
+
+        self.pt_to_ribs = Default::default();
+        for (rib_id, rib) in self.ribs.iter() {
+            Self::save_index(&mut self.pt_to_ribs, rib.0, *rib_id);
+            Self::save_index(&mut self.pt_to_ribs, rib.1, *rib_id);
+        }
+        self.rib_to_face = Default::default();
+        for (face_id, face) in self.faces.iter() {
+            face.update_rib_index(*face_id, &mut self.rib_to_face);
+        }
+        self.face_index = RTree::bulk_load(
+            self.faces
+                .iter()
+                .map(|(face_id, face)| FaceRtreeRecord(*face_id, *face.aabb()))
+                .collect(),
+        );
+
+        self.partially_split_faces = std::mem::take(&mut self.partially_split_faces)
+            .into_inner()
+            .into_iter()
+            .filter_map(|(face_id, face_ribs)| {
+                let face_ribs = face_ribs
+                    .iter()
+                    .filter_map(|rib_id| ribs.get(rib_id).copied())
+                    .collect_vec();
+                Some((*faces.get(&face_id)?, face_ribs)).filter(|(_, r)| !r.is_empty())
+            })
+            .collect();
+        self.face_splits = Default::default();
+        self.rib_parent = Default::default();
+        self.deleted_faces = Default::default();
+        self.split_ribs = Default::default();
+        self.rib_counter = self.ribs.len();
+        self.face_counter = self.faces.len();
+
+        Ok(CompactMap { faces, points })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn compact_keeps_meshes() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+        let mesh_id = index.difference(a, b).unwrap();
+        let faces_before = index.faces.len();
+        let polygons = mesh_id.make_ref(&index).all_polygons().len();
+
+        let ribs_before = index.ribs.len();
+        assert!(!index.deleted_faces.is_empty());
+
+        let map = index.compact().unwrap();
+        assert_eq!(map.faces.len(), index.faces.len());
+        assert!(index.faces.len() <= faces_before);
+        assert!(index.ribs.len() < ribs_before);
+        assert!(index.deleted_faces.is_empty());
+        // Seven corners of cube are left, and seven corners of the cut.
+        assert_eq!(map.points.len(), 14);
+        let mesh = mesh_id.make_ref(&index);
+        assert_eq!(mesh.all_polygons().len(), polygons);
+        assert!((mesh.volume() - 7.875).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+
+        let c = cube(&mut index, Vector3::new(-1.0, -1.0, -1.0), 1.0);
+        let mesh_id = index.difference(mesh_id, c).unwrap();
+        assert!((mesh_id.make_ref(&index).volume() - 7.75).abs() < 1e-9);
+    }
+}
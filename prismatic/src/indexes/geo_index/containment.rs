@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use math::{Scalar, Vector3};
+
+use super::{
+    face::FaceId, geo_object::GeoObject, mesh::MeshRef, poly_rtree::RaySelection,
+    raycast::distance_to_segment_sq, seg::SegmentDir,
+};
+
+/// Position of point relative to closed mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    Inside,
+    Outside,
+    OnBoundary,
+}
+
+impl<S: Scalar> MeshRef<'_, S> {
+    /// Tells, whether point is inside of mesh, outside of it, or lies on one of its polygons.
+    /// Mesh is expected to be closed. Point is inside, when ray from it crosses polygons of mesh
+    /// odd number of times. Rays, which pass through ribs or vertices of mesh, are not trusted -
+    /// other direction is taken then.
+    pub fn contains_point(&self, point: Vector3<S>) -> Containment {
+        let faces = self.face_poly_map().into_keys().collect::<HashSet<_>>();
+        self.faces_contain_point(point, &faces)
+    }
+
+    /// Same as `contains_point`, but only given faces bound the mesh. Faces are expected to form
+    /// closed shell.
+    pub(super) fn faces_contain_point(
+        &self,
+        point: Vector3<S>,
+        faces: &HashSet<FaceId>,
+    ) -> Containment {
+        (0..Self::RAYS)
+            .find_map(|ix| self.cast_containment_ray(point, Self::ray_dir(ix), faces))
+            // Every ray touched some rib - point is too close to mesh to tell.
+            .unwrap_or(Containment::OnBoundary)
+    }
+
+    const RAYS: usize = 32;
+
+    /// Directions, evenly spread over sphere and not aligned with axes, so that rays are unlikely
+    /// to go along polygons of axis aligned meshes.
+    fn ray_dir(ix: usize) -> Vector3<S> {
+        let golden_angle = std::f64::consts::PI * (3.0 - 5.0_f64.sqrt());
+        let z = 1.0 - (2 * ix + 1) as f64 / Self::RAYS as f64;
+        let r = (1.0 - z * z).sqrt();
+        let phi = 0.5 + golden_angle * ix as f64;
+        Vector3::new(
+            S::from_value(r * phi.cos()),
+            S::from_value(r * phi.sin()),
+            S::from_value(z),
+        )
+        .normalize()
+    }
+
+    /// Counts crossings of ray with faces of mesh. Returns `None`, when ray hits rib or vertex.
+    fn cast_containment_ray(
+        &self,
+        point: Vector3<S>,
+        dir: Vector3<S>,
+        faces: &HashSet<FaceId>,
+    ) -> Option<Containment> {
+        let tolerance = self.geo_index.tolerance();
+        let tolerance_sq = tolerance * tolerance;
+        let selection = RaySelection {
+            origin: point,
+            dir,
+            tolerance,
+        };
+
+        let mut crossings = 0;
+        for face_id in self
+            .geo_index
+            .face_index
+            .locate_with_selection_function(selection)
+            .map(|r| r.0)
+            .filter(|face_id| faces.contains(face_id))
+        {
+            let face = face_id.make_ref(self.geo_index);
+            let normal = face.plane().normal();
+            let distance = normal.dot(&point) - face.plane().d();
+            let segs = face.segments(SegmentDir::Fow).collect_vec();
+            let touches_rib = |pt: Vector3<S>| {
+                segs.iter()
+                    .any(|s| distance_to_segment_sq(pt, s.from(), s.to()) < tolerance_sq)
+            };
+
+            if distance.abs() < tolerance {
+                let projected = point - normal * distance;
+                if self.geo_index.face_contains_point(face_id, projected) {
+                    return Some(Containment::OnBoundary);
+                }
+            }
+
+            let along = normal.dot(&dir);
+            if along.abs() < tolerance {
+                if distance.abs() < tolerance {
+                    // Ray goes in plane of face.
+                    return None;
+                }
+                continue;
+            }
+            let t = -distance / along;
+            if t.is_negative() {
+                continue;
+            }
+            let hit = point + dir * t;
+            if touches_rib(hit) {
+                return None;
+            }
+            if self.geo_index.face_contains_point(face_id, hit) {
+                crossings += 1;
+            }
+        }
+
+        Some(if crossings % 2 == 1 {
+            Containment::Inside
+        } else {
+            Containment::Outside
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        containment::Containment,
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn contains_point_after_difference() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+        let result = index.difference(a, b).unwrap();
+        let mesh = result.make_ref(&index);
+
+        assert_eq!(
+            mesh.contains_point(Vector3::new(0.0, 0.0, 0.0)),
+            Containment::Inside
+        );
+        assert_eq!(
+            mesh.contains_point(Vector3::new(0.8, 0.8, 0.8)),
+            Containment::Outside
+        );
+        assert_eq!(
+            mesh.contains_point(Vector3::new(3.0, 0.0, 0.0)),
+            Containment::Outside
+        );
+        assert_eq!(
+            mesh.contains_point(Vector3::new(1.0, 0.0, 0.0)),
+            Containment::OnBoundary
+        );
+        assert_eq!(
+            mesh.contains_point(Vector3::new(0.5, 0.5, 0.5)),
+            Containment::OnBoundary
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use math::{Scalar, Vector3};
+use rstar::AABB;
+
+use super::{
+    containment::Containment,
+    face::FaceId,
+    geo_object::GeoObject,
+    index::GeoIndex,
+    mesh::{MeshId, MeshRef},
+    raycast::closest_point_on_segment,
+    seg::SegmentDir,
+};
+
+impl<S: Scalar> MeshRef<'_, S> {
+    /// Nearest point on polygons of mesh. Returns `None` for empty mesh.
+    pub fn closest_point(&self, point: Vector3<S>) -> Option<Vector3<S>> {
+        let faces = self.face_poly_map().into_keys().collect::<HashSet<_>>();
+        if faces.is_empty() {
+            return None;
+        }
+
+        // Cube around point grows until it catches some face. Any face, closer than the best
+        // point found, crosses cube with half size of that distance - so one more search with
+        // this size gives the answer.
+        let mut half_size = S::one();
+        loop {
+            let d = Vector3::new(half_size, half_size, half_size);
+            let envelope = AABB::from_corners((point - d).into(), (point + d).into());
+            let closest = self
+                .geo_index
+                .face_index
+                .locate_in_envelope_intersecting(&envelope)
+                .map(|record| record.0)
+                .filter(|face_id| faces.contains(face_id))
+                .map(|face_id| self.geo_index.closest_point_of_face(face_id, point))
+                .min_by(|a, b| {
+                    (*a - point)
+                        .magnitude_squared()
+                        .partial_cmp(&(*b - point).magnitude_squared())
+                        .expect("Distances are comparable")
+                });
+
+            match closest {
+                Some(closest) => {
+                    let distance = (closest - point).magnitude();
+                    if distance <= half_size {
+                        return Some(closest);
+                    }
+                    half_size = distance;
+                }
+                None => half_size *= S::two(),
+            }
+        }
+    }
+
+    /// Distance from point to polygons of mesh: negative inside of mesh, positive outside.
+    /// Returns `None` for empty mesh.
+    pub fn signed_distance(&self, point: Vector3<S>) -> Option<S> {
+        let distance = (self.closest_point(point)? - point).magnitude();
+        Some(match self.contains_point(point) {
+            Containment::Inside => -distance,
+            Containment::Outside => distance,
+            Containment::OnBoundary => S::zero(),
+        })
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Smallest distance between polygons of two meshes. It is zero, when polygons cross or touch
+    /// each other. Mesh, nested in other mesh without touching it, is at positive distance - use
+    /// `intersects` to detect this. Returns `None`, when one of meshes is empty.
+    pub fn min_distance(&self, a: MeshId, b: MeshId) -> Option<S> {
+        let faces_a = a.make_ref(self).face_poly_map().into_keys().collect_vec();
+        let faces_b = b
+            .make_ref(self)
+            .face_poly_map()
+            .into_keys()
+            .collect::<HashSet<_>>();
+        let first = faces_a.first()?;
+        let first_vertex = first
+            .make_ref(self)
+            .segments(SegmentDir::Fow)
+            .next()?
+            .from();
+        let mut best = (b.make_ref(self).closest_point(first_vertex)? - first_vertex).magnitude();
+
+        for face_a in faces_a {
+            // Only faces closer, than best distance found so far, are checked.
+            for face_b in self.faces_near(face_a, best, &faces_b) {
+                best = best.min(self.face_distance(face_a, face_b));
+                if best.is_zero() {
+                    return Some(best);
+                }
+            }
+        }
+        Some(best)
+    }
+
+    /// Checks, that two meshes cross, touch, or one of them is inside of other. Search stops at
+    /// first pair of crossing faces; no polygons are split.
+    pub fn intersects(&self, a: MeshId, b: MeshId) -> bool {
+        let tolerance = self.tolerance();
+        let faces_a = a.make_ref(self).face_poly_map().into_keys().collect_vec();
+        let faces_b = b
+            .make_ref(self)
+            .face_poly_map()
+            .into_keys()
+            .collect::<HashSet<_>>();
+        if faces_a.is_empty() || faces_b.is_empty() {
+            return false;
+        }
+        let touches = faces_a.iter().any(|&face_a| {
+            self.faces_near(face_a, tolerance, &faces_b)
+                .any(|face_b| self.face_distance(face_a, face_b) < tolerance)
+        });
+        touches || self.first_vertex_inside(a, b) || self.first_vertex_inside(b, a)
+    }
+
+    /// When meshes do not touch, one of them is inside the other, if any of its vertices is.
+    fn first_vertex_inside(&self, of_mesh: MeshId, by_mesh: MeshId) -> bool {
+        of_mesh
+            .make_ref(self)
+            .face_poly_map()
+            .into_keys()
+            .next()
+            .and_then(|face_id| face_id.make_ref(self).segments(SegmentDir::Fow).next())
+            .is_some_and(|s| {
+                by_mesh.make_ref(self).contains_point(s.from()) != Containment::Outside
+            })
+    }
+
+    /// Faces from the set, which bounding boxes are closer to bounding box of `face_id`, than
+    /// `distance`.
+    fn faces_near<'a>(
+        &'a self,
+        face_id: FaceId,
+        distance: S,
+        faces: &'a HashSet<FaceId>,
+    ) -> impl Iterator<Item = FaceId> + 'a {
+        let aabb = self.faces[&face_id].aabb();
+        let d = Vector3::new(distance, distance, distance);
+        let envelope = AABB::from_corners((aabb.min() - d).into(), (aabb.max() + d).into());
+        self.face_index
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|record| record.0)
+            .filter(move |face_id| faces.contains(face_id))
+            .collect_vec()
+            .into_iter()
+    }
+
+    /// Distance between two planar faces. Zero, when contour of one face crosses the other face.
+    /// Otherwise nearest points of faces are either vertex of one face and inner point of other,
+    /// or points on their contours.
+    fn face_distance(&self, a: FaceId, b: FaceId) -> S {
+        let segs_a = a.make_ref(self).segments(SegmentDir::Fow).collect_vec();
+        let segs_b = b.make_ref(self).segments(SegmentDir::Fow).collect_vec();
+        if segs_a
+            .iter()
+            .any(|s| self.segment_crosses_face(s.from(), s.to(), b))
+            || segs_b
+                .iter()
+                .any(|s| self.segment_crosses_face(s.from(), s.to(), a))
+        {
+            return S::zero();
+        }
+
+        let vertex_to_face = segs_a
+            .iter()
+            .map(|s| (s.from(), b))
+            .chain(segs_b.iter().map(|s| (s.from(), a)))
+            .map(|(pt, face_id)| {
+                (self.closest_point_of_face(face_id, pt) - pt).magnitude_squared()
+            });
+        let edge_to_edge = segs_a
+            .iter()
+            .cartesian_product(segs_b.iter())
+            .map(|(sa, sb)| segments_distance_sq(sa.from(), sa.to(), sb.from(), sb.to()));
+
+        vertex_to_face
+            .chain(edge_to_edge)
+            .fold(<S as num_traits::Bounded>::max_value(), |min, d| min.min(d))
+            .sqrt()
+    }
+
+    /// Checks, that segment goes through plane of face inside of it. Segments, lying in plane of
+    /// face, are not crossing it.
+    fn segment_crosses_face(&self, from: Vector3<S>, to: Vector3<S>, face_id: FaceId) -> bool {
+        let plane = self.faces[&face_id].plane();
+        let from_d = plane.normal().dot(&from) - plane.d();
+        let to_d = plane.normal().dot(&to) - plane.d();
+        if from_d.is_positive() == to_d.is_positive() && !from_d.is_zero() && !to_d.is_zero() {
+            return false;
+        }
+        if (from_d - to_d).is_zero() {
+            return false;
+        }
+        let pt = from.lerp(&to, from_d / (from_d - to_d));
+        self.face_contains_point(face_id, pt)
+    }
+
+    /// Projection of point to plane of face, or nearest point of face contour, when projection
+    /// is outside of face.
+    pub(super) fn closest_point_of_face(&self, face_id: FaceId, point: Vector3<S>) -> Vector3<S> {
+        let face = face_id.make_ref(self);
+        let plane = face.plane();
+        let projected = point - plane.normal() * (plane.normal().dot(&point) - plane.d());
+        if self.face_contains_point(face_id, projected) {
+            return projected;
+        }
+        face.segments(SegmentDir::Fow)
+            .map(|s| closest_point_on_segment(projected, s.from(), s.to()))
+            .min_by(|a, b| {
+                (*a - projected)
+                    .magnitude_squared()
+                    .partial_cmp(&(*b - projected).magnitude_squared())
+                    .expect("Distances are comparable")
+            })
+            .unwrap_or(projected)
+    }
+}
+
+/// Squared distance between nearest points of two segments.
+fn segments_distance_sq<S: Scalar>(
+    from_a: Vector3<S>,
+    to_a: Vector3<S>,
+    from_b: Vector3<S>,
+    to_b: Vector3<S>,
+) -> S {
+    let (dir_a, dir_b) = (to_a - from_a, to_b - from_b);
+    let r = from_a - from_b;
+    let (len_a, len_b) = (dir_a.magnitude_squared(), dir_b.magnitude_squared());
+    let (c, f) = (dir_a.dot(&r), dir_b.dot(&r));
+    let b = dir_a.dot(&dir_b);
+    let denom = len_a * len_b - b * b;
+
+    // Parameter on first segment for nearest points of lines; any one for parallel segments.
+    let s = if denom.is_zero() {
+        S::zero()
+    } else {
+        ((b * f - c * len_b) / denom).max(S::zero()).min(S::one())
+    };
+    let t = (b * s + f) / len_b;
+    let (s, t) = if t.is_negative() {
+        ((-c / len_a).max(S::zero()).min(S::one()), S::zero())
+    } else if t > S::one() {
+        (((b - c) / len_a).max(S::zero()).min(S::one()), S::one())
+    } else {
+        (s, t)
+    };
+    ((from_a + dir_a * s) - (from_b + dir_b * t)).magnitude_squared()
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn signed_distance_to_cube() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let mesh = a.make_ref(&index);
+
+        let inside = mesh.signed_distance(Vector3::new(0.2, 0.5, 0.0)).unwrap();
+        assert!((inside + 0.5).abs() < 1e-9);
+        let outside = mesh.signed_distance(Vector3::new(4.0, 0.0, 0.0)).unwrap();
+        assert!((outside - 3.0).abs() < 1e-9);
+        let corner = mesh.signed_distance(Vector3::new(2.0, 2.0, 2.0)).unwrap();
+        assert!((corner - 3.0_f64.sqrt()).abs() < 1e-9);
+        let closest = mesh.closest_point(Vector3::new(2.0, 2.0, 2.0)).unwrap();
+        assert!((closest - Vector3::new(1.0, 1.0, 1.0)).magnitude() < 1e-9);
+        assert_eq!(mesh.signed_distance(Vector3::new(1.0, 0.3, 0.0)), Some(0.0));
+    }
+
+    #[test]
+    fn distance_between_meshes() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let far = cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+        let diagonal = cube(&mut index, Vector3::new(-3.0, 3.0, 0.0), 2.0);
+        let crossing = cube(&mut index, Vector3::new(0.0, -1.0, 1.0), 1.0);
+        let nested = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 0.5);
+
+        let distance = |b| index.min_distance(a, b).unwrap();
+        assert!((distance(far) - 2.5).abs() < 1e-9);
+        assert!((distance(diagonal) - 2.0_f64.sqrt()).abs() < 1e-9);
+        assert!(distance(crossing).abs() < 1e-9);
+        assert!((distance(nested) - 0.75).abs() < 1e-9);
+
+        assert!(!index.intersects(a, far));
+        assert!(!index.intersects(a, diagonal));
+        assert!(index.intersects(a, crossing));
+        assert!(index.intersects(a, nested));
+    }
+}
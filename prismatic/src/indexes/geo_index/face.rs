@@ -16,12 +16,15 @@ use super::{
     index::GeoIndex,
     rib::RibId,
     seg::{Seg, SegRef, SegmentDir},
+    transaction::MapMut,
 };
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FaceId(pub usize);
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Face<S: Scalar> {
     segments: Vec<Seg>,
     aabb: Aabb<S>,
@@ -94,6 +97,19 @@ impl<S: Scalar> Face<S> {
         }
     }
 
+    /// Same face, which goes through ribs with new ids.
+    pub(crate) fn with_renamed_ribs(&self, ribs: &BTreeMap<RibId, RibId>) -> Self {
+        let segments = self
+            .segments
+            .iter()
+            .map(|s| Seg {
+                rib_id: ribs[&s.rib_id],
+                dir: s.dir,
+            })
+            .collect();
+        Self::create(segments, self.plane.clone(), self.aabb)
+    }
+
     pub(crate) fn plane(&self) -> &Plane<S> {
         &self.plane
     }
@@ -123,7 +139,7 @@ impl<S: Scalar> Face<S> {
     pub(crate) fn update_rib_index(
         &self,
         my_id: FaceId,
-        rib_index: &mut BTreeMap<RibId, Vec<FaceId>>,
+        rib_index: &mut impl MapMut<Key = RibId, Value = Vec<FaceId>>,
     ) {
         for s in &self.segments {
             GeoIndex::<S>::save_index(rib_index, s.rib_id, my_id);
@@ -133,7 +149,7 @@ impl<S: Scalar> Face<S> {
     pub(crate) fn delete_me_from_rib_index(
         &self,
         my_id: FaceId,
-        rib_index: &mut BTreeMap<RibId, Vec<FaceId>>,
+        rib_index: &mut impl MapMut<Key = RibId, Value = Vec<FaceId>>,
     ) {
         for s in &self.segments {
             GeoIndex::<S>::remove_item_from_index(rib_index, &s.rib_id, &my_id);
@@ -291,6 +307,16 @@ impl<'a, S: Scalar> FaceRef<'a, S> {
             .map(|seg| self.index.load_segref(seg))
     }
 
+    /// Segments of face without bridges. Bridges connect holes of the face with its outer
+    /// contour; they are used by the face twice and do not bound it.
+    pub(crate) fn non_bridge_segments(&self, dir: SegmentDir) -> Vec<SegRef<'a, S>> {
+        let segs = self.segments(dir).collect_vec();
+        let counts = segs.iter().map(|s| s.rib_id()).counts();
+        segs.into_iter()
+            .filter(|s| counts[&s.rib_id()] == 1)
+            .collect()
+    }
+
     pub(crate) fn plane(&self) -> &Plane<S> {
         self.index.faces[&self.face_id].plane()
     }
@@ -298,6 +324,105 @@ impl<'a, S: Scalar> FaceRef<'a, S> {
     pub(crate) fn aabb(&self) -> &Aabb<S> {
         self.index.faces[&self.face_id].aabb()
     }
+
+    /// Triangles of face, oriented same way, as face segments. Every vertex of face contour is
+    /// used, so points, where neighbouring faces were split, stay on triangle edges.
+    pub fn triangulate(&self) -> anyhow::Result<Vec<[Vector3<S>; 3]>> {
+        Ok(self
+            .triangles()?
+            .into_iter()
+            .map(|triangle| triangle.map(|pt| self.index.vertices.get_point(pt)))
+            .collect())
+    }
+
+    /// Splits face into triangles, which are oriented same way, as face segments.
+    /// Holes of the face are connected to outer contour with bridges - ribs, which are used by
+    /// face twice. Bridges are not used as constraints, and triangles, which are outside of
+    /// face contour, are dropped.
+    pub(crate) fn triangles(&self) -> anyhow::Result<Vec<[PtId; 3]>> {
+        let contour = self
+            .segments(SegmentDir::Fow)
+            .map(|s| (s.rib_id, s.from_pt(), s.to_pt()))
+            .collect_vec();
+        if contour.len() == 3 {
+            return Ok(vec![[contour[0].1, contour[1].1, contour[2].1]]);
+        }
+
+        let pts = contour
+            .iter()
+            .map(|(_, from, _)| *from)
+            .unique()
+            .collect_vec();
+        let pt_ix: BTreeMap<PtId, usize> =
+            pts.iter().enumerate().map(|(ix, pt)| (*pt, ix)).collect();
+        let edges = self
+            .non_bridge_segments(SegmentDir::Fow)
+            .iter()
+            .map(|s| (pt_ix[&s.from_pt()], pt_ix[&s.to_pt()]))
+            .collect_vec();
+
+        let basis = self.calculate_2d_basis();
+        let pts2d = pts
+            .iter()
+            .map(|pt| {
+                let v = basis.project_on_plane_z(&self.index.vertices.get_point(*pt));
+                (
+                    v.x.to_f64().unwrap_or_default(),
+                    v.y.to_f64().unwrap_or_default(),
+                )
+            })
+            .collect_vec();
+
+        let triangles = triangulate_contours(&pts2d, &edges)
+            .map_err(|err| anyhow::anyhow!("Cannot triangulate face {:?}: {err}", self.face_id))?;
+
+        Ok(triangles
+            .into_iter()
+            .map(|triangle| triangle.map(|ix| pts[ix]))
+            .collect())
+    }
+}
+
+/// Triangulates area, bounded by contours in 2d: outer contours and holes. Triangles, which are
+/// outside of contours, are dropped; others go counter-clockwise.
+fn triangulate_contours(
+    pts: &[(f64, f64)],
+    edges: &[(usize, usize)],
+) -> Result<Vec<[usize; 3]>, cdt::Error> {
+    Ok(cdt::triangulate_with_edges(pts, edges)?
+        .into_iter()
+        .filter(|&(a, b, c)| {
+            let x = (pts[a].0 + pts[b].0 + pts[c].0) / 3.0;
+            let y = (pts[a].1 + pts[b].1 + pts[c].1) / 3.0;
+            is_inside_contours((x, y), pts, edges)
+        })
+        .map(|(a, b, c)| {
+            let (pa, pb, pc) = (pts[a], pts[b], pts[c]);
+            let area = (pb.0 - pa.0) * (pc.1 - pa.1) - (pb.1 - pa.1) * (pc.0 - pa.0);
+            if area < 0.0 {
+                [a, c, b]
+            } else {
+                [a, b, c]
+            }
+        })
+        .collect())
+}
+
+/// Even-odd test: point is inside, when ray from it crosses contour edges odd number of times.
+fn is_inside_contours(pt: (f64, f64), pts: &[(f64, f64)], edges: &[(usize, usize)]) -> bool {
+    edges
+        .iter()
+        .filter(|&&(from, to)| {
+            let (a, b) = (pts[from], pts[to]);
+            if (a.1 > pt.1) == (b.1 > pt.1) {
+                return false;
+            }
+            let x = a.0 + (pt.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+            x > pt.0
+        })
+        .count()
+        % 2
+        == 1
 }
 
 impl<'a, S: Scalar> UnRef<'a, S> for FaceRef<'a, S> {
@@ -335,3 +460,54 @@ impl<'a, S: Scalar + 'a> GeoObject<'a, S> for FaceId {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use math::{CrossProduct, Vector3};
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject, mesh::tests::new_index, seg::SegmentDir,
+    };
+
+    #[test]
+    fn triangles_of_face_with_hole() {
+        let mut index = new_index();
+        let mesh_id = index.new_mesh();
+        let v = |x: f64, y: f64| Vector3::new(x, y, 0.0);
+        // Hole is connected to outer contour with bridge, which goes there and back.
+        let keyhole = [
+            v(-2.0, -2.0),
+            v(2.0, -2.0),
+            v(2.0, 2.0),
+            v(-2.0, 2.0),
+            v(-2.0, -2.0),
+            v(-1.0, -1.0),
+            v(-1.0, 1.0),
+            v(1.0, 1.0),
+            v(1.0, -1.0),
+            v(-1.0, -1.0),
+        ];
+        mesh_id
+            .make_mut_ref(&mut index)
+            .add_polygon(&keyhole)
+            .unwrap();
+        let mesh = mesh_id.make_ref(&index);
+        let poly = mesh.all_polygons()[0].make_ref(&index);
+        let face = poly.face_id().make_ref(&index);
+        assert_eq!(face.segments(SegmentDir::Fow).count(), 10);
+
+        // Eight points and one hole give eight triangles.
+        let triangles = face.triangulate().unwrap();
+        assert_eq!(triangles.len(), 8);
+        assert_eq!(mesh.triangulated().unwrap().len(), 8);
+        let area: f64 = triangles
+            .iter()
+            .map(|[a, b, c]| (b - a).cross_product(&(c - a)).magnitude() / 2.0)
+            .sum();
+        assert!((area - 12.0).abs() < 1e-9);
+        for [a, b, c] in triangles {
+            let center = (a + b + c) / 3.0;
+            assert!(center.x.abs() > 1.0 || center.y.abs() > 1.0);
+        }
+    }
+}
@@ -33,6 +33,7 @@ use super::poly::{Poly, PolyId, PolyRefMut, UnrefPoly};
 use super::poly_rtree::FaceRtreeRecord;
 use super::rib::RibRef;
 use super::seg::SegmentRef;
+use super::transaction::{Journaled, MapMut, TransactionStart};
 use super::{
     mesh::{MeshId, MeshRef},
     rib::{Rib, RibId},
@@ -43,22 +44,23 @@ use super::{
 pub struct GeoIndex<S: Scalar> {
     pub(crate) vertices: VertexIndex<S>,
     pub(super) face_index: RTree<FaceRtreeRecord<S>>,
-    pub(super) ribs: BTreeMap<RibId, Rib>,
-    pub(super) faces: BTreeMap<FaceId, Face<S>>,
-    pub(super) meshes: HashMap<MeshId, Mesh>,
-    pub(super) pt_to_ribs: BTreeMap<PtId, Vec<RibId>>,
-    pub(super) rib_to_face: BTreeMap<RibId, Vec<FaceId>>,
-    pub(super) partially_split_faces: BTreeMap<FaceId, Vec<RibId>>,
-    pub(super) face_splits: BTreeMap<FaceId, Vec<FaceId>>,
-    pub(super) rib_parent: BTreeMap<RibId, RibId>,
-    pub(super) deleted_faces: BTreeMap<FaceId, Face<S>>,
-    pub(super) split_ribs: BTreeMap<RibId, Vec<RibId>>,
+    pub(super) ribs: Journaled<BTreeMap<RibId, Rib>>,
+    pub(super) faces: Journaled<BTreeMap<FaceId, Face<S>>>,
+    pub(super) meshes: Journaled<HashMap<MeshId, Mesh>>,
+    pub(super) pt_to_ribs: Journaled<BTreeMap<PtId, Vec<RibId>>>,
+    pub(super) rib_to_face: Journaled<BTreeMap<RibId, Vec<FaceId>>>,
+    pub(super) partially_split_faces: Journaled<BTreeMap<FaceId, Vec<RibId>>>,
+    pub(super) face_splits: Journaled<BTreeMap<FaceId, Vec<FaceId>>>,
+    pub(super) rib_parent: Journaled<BTreeMap<RibId, RibId>>,
+    pub(super) deleted_faces: Journaled<BTreeMap<FaceId, Face<S>>>,
+    pub(super) split_ribs: Journaled<BTreeMap<RibId, Vec<RibId>>>,
     face_split_debug: BTreeMap<FaceId, Option<FaceId>>,
-    input_polygon_min_rib_length: S,
-    points_precision: S,
-    rib_counter: usize,
-    face_counter: usize,
-    mesh_counter: usize,
+    pub(super) input_polygon_min_rib_length: S,
+    pub(super) points_precision: S,
+    pub(super) rib_counter: usize,
+    pub(super) face_counter: usize,
+    pub(super) mesh_counter: usize,
+    pub(super) transaction: Option<TransactionStart>,
     current_color: usize,
     debug_path: PathBuf,
 }
@@ -88,6 +90,7 @@ where
             rib_counter: Default::default(),
             face_counter: Default::default(),
             mesh_counter: Default::default(),
+            transaction: None,
             face_split_debug: BTreeMap::new(),
 
             current_color: 0,
@@ -119,6 +122,16 @@ where
         self
     }
 
+    /// Distance, at which points are taken as coincident in geometric queries: points
+    /// precision of index, or one micrometer, if precision is not set.
+    pub(crate) fn tolerance(&self) -> S {
+        if self.points_precision > S::zero() {
+            self.points_precision
+        } else {
+            S::from_value(0.001)
+        }
+    }
+
     fn get_next_rib_id(&mut self) -> RibId {
         self.rib_counter += 1;
         RibId(self.rib_counter)
@@ -822,7 +835,7 @@ where
 
         new_ids
     }
-     fn split_rib_in_face_using_indexed_pts(
+    pub(super) fn split_rib_in_face_using_indexed_pts(
         &mut self,
         pts: &[PtId],
         rib_id: RibId,
@@ -1100,10 +1113,13 @@ where
         self.vertices.get_or_insert_point(pt, self.points_precision)
     }
 
-    pub(crate) fn save_index<Ix, Item>(index: &mut BTreeMap<Ix, Vec<Item>>, ix: Ix, item: Item)
-    where
-        Ix: Hash + Ord,
-        Item: PartialEq,
+    pub(crate) fn save_index<Ix, Item>(
+        index: &mut impl MapMut<Key = Ix, Value = Vec<Item>>,
+        ix: Ix,
+        item: Item,
+    ) where
+        Ix: Hash + Ord + Clone,
+        Item: PartialEq + Clone,
     {
         if let Some(items) = index.get_mut(&ix) {
             if !items.contains(&item) {
@@ -1115,12 +1131,12 @@ where
     }
 
     pub(crate) fn remove_item_from_index<Ix, Item>(
-        index: &mut BTreeMap<Ix, Vec<Item>>,
+        index: &mut impl MapMut<Key = Ix, Value = Vec<Item>>,
         ix: &Ix,
         item: &Item,
     ) where
-        Ix: Hash + Ord,
-        Item: PartialEq,
+        Ix: Hash + Ord + Clone,
+        Item: PartialEq + Clone,
     {
         if let Some(items) = index.get_mut(ix) {
             items.retain(|i| i != item)
@@ -1280,7 +1296,7 @@ where
         }
     }
 
-    fn remove_rib(&mut self, rib_id: RibId) {
+    pub(super) fn remove_rib(&mut self, rib_id: RibId) {
         if self.rib_to_face.get(&rib_id).is_some_and(|v| !v.is_empty()) {
             panic!("rib index to poly is not empty");
         }
@@ -1875,10 +1891,27 @@ where
         by_mesh: MeshId,
         filter: PolygonFilter,
     ) -> Vec<UnrefPoly> {
+        self.classify_polygons(of_mesh, by_mesh)
+            .into_iter()
+            .filter(|(_, r)| *r == filter)
+            .map(|(poly_id, _)| UnrefPoly {
+                mesh_id: of_mesh,
+                poly_id,
+            })
+            .collect_vec()
+    }
+
+    /// Marks polygons of `of_mesh` as `Front`, `Back` or `Shared` relative to `by_mesh`.
+    /// Polygons, which are not connected to any common rib of two meshes, are not marked at all.
+    pub(crate) fn classify_polygons(
+        &self,
+        of_mesh: MeshId,
+        by_mesh: MeshId,
+    ) -> BTreeMap<PolyId, PolygonFilter> {
         let _ts = SystemTime::now();
         let _t = SystemTime::now();
         let mut face_mesh_index = BTreeMap::new();
-        for (mesh_id, mesh) in &self.meshes {
+        for (mesh_id, mesh) in self.meshes.iter() {
             if [of_mesh, by_mesh].contains(mesh_id) {
                 for p in mesh.polies.keys() {
                     let face_id = UnrefPoly {
@@ -1939,18 +1972,6 @@ where
             }
         }
 
-        if matches!(filter, PolygonFilter::Shared) {
-            // early return for shareds
-            let collect_vec = visited
-                .into_iter()
-                .filter(|(_, r)| *r == filter)
-                .map(|(poly_id, _)| UnrefPoly {
-                    mesh_id: of_mesh,
-                    poly_id,
-                })
-                .collect_vec();
-            return collect_vec;
-        }
         let _t = SystemTime::now();
 
         let mut ribs = HashSet::with_capacity(ribs_with_faces.len());
@@ -2008,16 +2029,7 @@ where
             }
         }
         let _t = SystemTime::now();
-        let visited = self.spread_visited_around(&ribs, of_mesh, visited);
-        
-        visited
-            .into_iter()
-            .filter(|(_, r)| *r == filter)
-            .map(|(poly_id, _)| UnrefPoly {
-                mesh_id: of_mesh,
-                poly_id,
-            })
-            .collect_vec()
+        self.spread_visited_around(&ribs, of_mesh, visited)
     }
 
     fn spread_visited_around(
@@ -2219,6 +2231,23 @@ where
         format!("points={points};\n polyhedron(points, [{hedras}]);")
     }
 
+    /// Writes each mesh as separate OpenSCAD module, named after mesh id, and draws it with its own
+    /// color.
+    pub fn scad_multi(&self) -> String {
+        let colors = ["red", "green", "blue", "orange", "purple", "cyan", "magenta", "yellow"];
+        self.meshes
+            .keys()
+            .sorted()
+            .enumerate()
+            .map(|(ix, mesh_id)| {
+                let name = format!("mesh_{}", mesh_id.0);
+                let polyhedron = mesh_id.make_ref(self).scad();
+                let color = colors[ix % colors.len()];
+                format!("module {name}() {{\n {polyhedron}\n}}\ncolor(\"{color}\") {name}();")
+            })
+            .join("\n\n")
+    }
+
     fn is_chain_inside_face(&self, chain: &[Seg], face_id: FaceId) -> bool {
         chain
             .iter()
@@ -2390,7 +2419,7 @@ where
             + hits_points_new.iter().filter_map(|hp| hp.right()).count()
     }
 
-    fn collect_line_face_intersections(
+    pub(super) fn collect_line_face_intersections(
         &self,
         line: Line<S>,
         face_id: FaceId,
@@ -0,0 +1,264 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use itertools::Itertools;
+use math::Scalar;
+
+use crate::indexes::vertex_index::PtId;
+
+use super::{
+    face::FaceId,
+    geo_object::GeoObject,
+    index::GeoIndex,
+    mesh::MeshRefMut,
+    rib::RibId,
+    seg::{Seg, SegRef, SegmentDir},
+    transaction::MapMut,
+};
+
+impl<S: Scalar> MeshRefMut<'_, S> {
+    /// Makes faces of mesh meet each other at common ribs, so that no vertex lies inside of
+    /// neighbouring rib. Face, which still goes along rib, split for other faces, gets parts of
+    /// this rib instead. Rib with vertex of mesh inside of it is split at this vertex in every
+    /// face, which uses it.
+    /// Returns number of ribs, which were replaced with their parts.
+    pub fn resolve_t_junctions(&mut self) -> usize {
+        let faces = self
+            .mesh_id
+            .make_ref(self.geo_index)
+            .face_poly_map()
+            .into_keys()
+            .collect::<HashSet<_>>();
+
+        let mut resolved = 0;
+        for face_id in faces.iter().sorted() {
+            resolved += self.geo_index.use_split_ribs(*face_id);
+        }
+        for face_id in faces.iter().sorted() {
+            resolved += self.geo_index.split_ribs_at_vertices(*face_id, &faces);
+        }
+        resolved
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Replaces ribs of face, which were split, with their current parts.
+    fn use_split_ribs(&mut self, face_id: FaceId) -> usize {
+        let segs = self.faces[&face_id]
+            .segments(SegmentDir::Fow)
+            .copied()
+            .collect_vec();
+        let len = segs.len();
+
+        let mut replaced = 0;
+        // Going from the end keeps positions of segments, which are not replaced yet.
+        for ix in (0..len).rev() {
+            let seg = segs[ix];
+            if !self.split_ribs.contains_key(&seg.rib_id) {
+                continue;
+            }
+            let parts: HashMap<RibId, (PtId, PtId)> = self
+                .get_ribs_with_root_parent(seg.rib_id)
+                .into_iter()
+                .filter(|rib_id| *rib_id != seg.rib_id)
+                .map(|rib_id| (rib_id, (self.ribs[&rib_id].0, self.ribs[&rib_id].1)))
+                .collect();
+            let ends = parts
+                .values()
+                .flat_map(|(a, b)| [*a, *b])
+                .counts()
+                .into_iter()
+                .filter(|(_, count)| count % 2 == 1)
+                .map(|(pt, _)| pt)
+                .sorted()
+                .collect_vec();
+            let [first, last] = ends[..] else {
+                continue;
+            };
+
+            // Rib itself may be gone - then its start is found by neighbouring segments.
+            let touches = |s: Seg, pt: PtId| {
+                self.ribs
+                    .get(&s.rib_id)
+                    .is_some_and(|r| r.0 == pt || r.1 == pt)
+            };
+            let (prev, next) = (segs[(ix + len - 1) % len], segs[(ix + 1) % len]);
+            let mut from = if self.ribs.contains_key(&seg.rib_id) {
+                seg.from(&self.ribs)
+            } else if touches(prev, last) || touches(next, first) {
+                last
+            } else {
+                first
+            };
+
+            let mut left = parts;
+            let mut replacement = Vec::new();
+            while let Some((&rib_id, &(a, b))) =
+                left.iter().find(|(_, (a, b))| *a == from || *b == from)
+            {
+                left.remove(&rib_id);
+                let dir = if a == from {
+                    SegmentDir::Fow
+                } else {
+                    SegmentDir::Rev
+                };
+                replacement.push(Seg { rib_id, dir });
+                from = if a == from { b } else { a };
+            }
+            let goes_to_end = !self.ribs.contains_key(&seg.rib_id) || from == seg.to(&self.ribs);
+            if !left.is_empty() || !goes_to_end {
+                continue;
+            }
+
+            for s in &replacement {
+                Self::save_index(&mut self.rib_to_face, s.rib_id, face_id);
+            }
+            Self::remove_item_from_index(&mut self.rib_to_face, &seg.rib_id, &face_id);
+            if let Some(face) = self.faces.get_mut(&face_id) {
+                face.replace_segments(ix, replacement);
+            }
+            if !self.rib_to_face.contains_key(&seg.rib_id) {
+                self.remove_rib(seg.rib_id);
+            }
+            replaced += 1;
+        }
+        replaced
+    }
+
+    /// Splits ribs of face at vertices of nearby faces from the set, which lie inside of them.
+    /// Rib is split in every face, which uses it.
+    fn split_ribs_at_vertices(&mut self, face_id: FaceId, faces: &HashSet<FaceId>) -> usize {
+        let aabb = *self.faces[&face_id].aabb();
+        let pts = self
+            .face_index
+            .locate_in_envelope_intersecting(&aabb.into())
+            .map(|record| record.0)
+            .filter(|f| faces.contains(f))
+            .collect_vec()
+            .into_iter()
+            .flat_map(|f| {
+                f.make_ref(self)
+                    .segments(SegmentDir::Fow)
+                    .map(|s| s.from_pt())
+                    .collect_vec()
+            })
+            .collect::<BTreeSet<_>>();
+
+        let mut split = 0;
+        while let Some((rib_id, inner)) = self.rib_with_inner_points(face_id, &pts) {
+            for face_id in self.rib_to_face.remove(&rib_id).into_iter().flatten() {
+                let new_ribs = self.split_rib_in_face_using_indexed_pts(&inner, rib_id, face_id);
+                new_ribs
+                    .iter()
+                    .for_each(|r| Self::save_index(&mut self.split_ribs, rib_id, *r));
+            }
+            self.remove_rib(rib_id);
+            split += 1;
+        }
+        split
+    }
+
+    /// First rib of face, which has some of points inside of it, together with these points.
+    fn rib_with_inner_points(
+        &self,
+        face_id: FaceId,
+        pts: &BTreeSet<PtId>,
+    ) -> Option<(RibId, Vec<PtId>)> {
+        // Bridges are used twice by the face; they are left as they are.
+        face_id
+            .make_ref(self)
+            .non_bridge_segments(SegmentDir::Fow)
+            .iter()
+            .find_map(|s| {
+                let inner = self.inner_points(s, pts);
+                (!inner.is_empty()).then_some((s.rib_id(), inner))
+            })
+    }
+
+    /// Points from the set, which lie inside of segment, not at its ends.
+    pub(super) fn inner_points(&self, seg: &SegRef<'_, S>, pts: &BTreeSet<PtId>) -> Vec<PtId> {
+        let vertex_pulling = self.tolerance();
+        let vertex_pulling_sq = vertex_pulling * vertex_pulling;
+        let (from, dir) = (seg.from(), seg.to() - seg.from());
+        pts.iter()
+            .filter(|pt| **pt != seg.from_pt() && **pt != seg.to_pt())
+            .filter(|pt| {
+                let v = self.vertices.get_point(**pt) - from;
+                let t = v.dot(&dir) / dir.magnitude_squared();
+                t > S::zero()
+                    && t < S::one()
+                    && (v - dir * t).magnitude_squared() < vertex_pulling_sq
+            })
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        index::GeoIndex,
+        mesh::{
+            tests::{cube, new_index},
+            MeshId,
+        },
+        rib::RibId,
+    };
+
+    /// Cube, which rib is split at its middle only in one of two faces around it, as if
+    /// neighbour face was left behind. Returns the rib, which was split.
+    fn half_split_cube(index: &mut GeoIndex<f64>, record_split: bool) -> (MeshId, RibId) {
+        let mesh_id = cube(index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let (rib_id, face_id) = index
+            .rib_to_face
+            .iter()
+            .find_map(|(rib_id, faces)| Some((*rib_id, *faces.first()?)))
+            .unwrap();
+        let rib = index.ribs[&rib_id];
+        let middle = (index.vertices.get_point(rib.0) + index.vertices.get_point(rib.1)) / 2.0;
+        let pt = index.vertices.get_or_insert_point(middle, index.points_precision);
+
+        let parts = index.split_rib_in_face_using_indexed_pts(&[pt], rib_id, face_id);
+        GeoIndex::<f64>::remove_item_from_index(&mut index.rib_to_face, &rib_id, &face_id);
+        if record_split {
+            for part in parts {
+                GeoIndex::<f64>::save_index(&mut index.split_ribs, rib_id, part);
+            }
+        }
+        (mesh_id, rib_id)
+    }
+
+    #[test]
+    fn neighbour_uses_split_rib() {
+        let mut index = new_index();
+        let (mesh_id, rib_id) = half_split_cube(&mut index, true);
+        let report = mesh_id.make_ref(&index).validate();
+        assert_eq!(report.open_ribs.len(), 3);
+        assert!(report.open_ribs.contains(&rib_id));
+        // Middle of the rib is a vertex of one face, but lies inside of rib of the other.
+        assert_eq!(report.isolated_vertices.len(), 1);
+
+        assert_eq!(mesh_id.make_mut_ref(&mut index).resolve_t_junctions(), 1);
+        let mesh = mesh_id.make_ref(&index);
+        assert!(mesh.validate().is_valid());
+        assert!((mesh.volume() - 8.0).abs() < 1e-9);
+        assert!(!index.ribs.contains_key(&rib_id));
+    }
+
+    #[test]
+    fn rib_is_split_at_vertex_of_neighbour() {
+        let mut index = new_index();
+        let (mesh_id, rib_id) = half_split_cube(&mut index, false);
+        let report = mesh_id.make_ref(&index).validate();
+        assert_eq!(report.open_ribs.len(), 3);
+        assert_eq!(report.isolated_vertices.len(), 1);
+
+        assert_eq!(mesh_id.make_mut_ref(&mut index).resolve_t_junctions(), 1);
+        let mesh = mesh_id.make_ref(&index);
+        assert!(mesh.validate().is_valid());
+        assert!((mesh.volume() - 8.0).abs() < 1e-9);
+        assert!(!index.ribs.contains_key(&rib_id));
+    }
+}
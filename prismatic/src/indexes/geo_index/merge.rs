@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use math::Scalar;
+
+use crate::{indexes::vertex_index::PtId, planar::plane::Plane};
+
+use super::{
+    geo_object::GeoObject,
+    index::GeoIndex,
+    mesh::{MeshId, MeshRefMut},
+    poly::PolyId,
+    rib::RibId,
+    slice::{chain_cuts, plane_basis},
+    split::{bridge_holes, convex_corner, Pt2},
+};
+
+impl<S: Scalar> MeshRefMut<'_, S> {
+    /// Joins neighbouring polygons, which lie in one plane and look the same way, into one
+    /// polygon, dropping ribs between them. Holes, which appear inside of joined polygon, are
+    /// connected to its outer contour with bridges. Group of polygons, which contour cannot be
+    /// built, is left as is.
+    /// Returns number of polygons, which are gone.
+    pub fn merge_coplanar_faces(&mut self) -> anyhow::Result<usize> {
+        let mesh_id = self.mesh_id;
+        let polies = self.all_polygons();
+        let index = &mut *self.geo_index;
+        let tolerance = index.tolerance();
+
+        let mut planes: HashMap<PolyId, Plane<S>> = HashMap::new();
+        let mut rib_polies: BTreeMap<RibId, Vec<PolyId>> = BTreeMap::new();
+        for poly in &polies {
+            let poly_ref = poly.make_ref(index);
+            planes.insert(poly.poly_id, poly_ref.plane());
+            // Bridges are used twice by same polygon, they do not connect it to others.
+            for s in poly_ref.non_bridge_segments() {
+                rib_polies.entry(s.rib_id()).or_default().push(poly.poly_id);
+            }
+        }
+        let coplanar = |a: &Plane<S>, b: &Plane<S>| {
+            (a.normal() - b.normal()).magnitude() < tolerance && (a.d() - b.d()).abs() < tolerance
+        };
+        let mut neighbours: HashMap<PolyId, Vec<PolyId>> = HashMap::new();
+        for (a, b) in rib_polies
+            .values()
+            .filter_map(|polies| polies.iter().copied().collect_tuple())
+            .filter(|(a, b)| coplanar(&planes[a], &planes[b]))
+        {
+            neighbours.entry(a).or_default().push(b);
+            neighbours.entry(b).or_default().push(a);
+        }
+
+        let mut visited = HashSet::new();
+        let mut groups = Vec::new();
+        for poly in &polies {
+            if !visited.insert(poly.poly_id) {
+                continue;
+            }
+            let mut group = vec![poly.poly_id];
+            let mut queue = VecDeque::from([poly.poly_id]);
+            while let Some(poly_id) = queue.pop_front() {
+                for next in neighbours.get(&poly_id).into_iter().flatten() {
+                    if visited.insert(*next) {
+                        group.push(*next);
+                        queue.push_back(*next);
+                    }
+                }
+            }
+            if group.len() > 1 {
+                groups.push(group);
+            }
+        }
+
+        let mut merged = 0;
+        for group in groups {
+            let plane = planes[&group[0]].clone();
+            let Some(contour) = index.merged_contour(mesh_id, &group, &plane) else {
+                continue;
+            };
+            let vertices = contour
+                .iter()
+                .map(|pt| index.vertices.get_point(*pt))
+                .collect_vec();
+            for poly_id in &group {
+                index.remove_polygon(*poly_id, mesh_id);
+            }
+            index.add_polygon_to_mesh_no_intersect(&vertices, mesh_id)?;
+            merged += group.len() - 1;
+        }
+        Ok(merged)
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Contour around group of polygons in one plane: ribs, used by only one polygon of group,
+    /// chained together. Each hole is joined to the contour with a bridge - rib, which contour goes
+    /// through in both directions. Contour starts at convex corner, see `convex_corner`.
+    fn merged_contour(
+        &self,
+        mesh_id: MeshId,
+        polies: &[PolyId],
+        plane: &Plane<S>,
+    ) -> Option<Vec<PtId>> {
+        let mut ribs: BTreeMap<RibId, Vec<(PtId, PtId)>> = BTreeMap::new();
+        for poly_id in polies {
+            for s in self
+                .load_polygon_ref(mesh_id, *poly_id)
+                .non_bridge_segments()
+            {
+                ribs.entry(s.rib_id())
+                    .or_default()
+                    .push((s.from_pt(), s.to_pt()));
+            }
+        }
+        let mut cuts: BTreeMap<PtId, Vec<PtId>> = BTreeMap::new();
+        for (from, to) in ribs
+            .into_values()
+            .filter(|usage| usage.len() == 1)
+            .map(|usage| usage[0])
+        {
+            cuts.entry(from).or_default().push(to);
+        }
+
+        let basis = plane_basis(plane);
+        let pts: HashMap<PtId, Pt2> = cuts
+            .keys()
+            .map(|pt| {
+                let v = basis.project_on_plane_z(&self.vertices.get_point(*pt));
+                let x = v.x.to_f64().unwrap_or_default();
+                let y = v.y.to_f64().unwrap_or_default();
+                (*pt, (x, y))
+            })
+            .collect();
+
+        let mut outer = None;
+        let mut holes = Vec::new();
+        for (contour, closed) in chain_cuts(cuts) {
+            if !closed {
+                return None;
+            }
+            let area = contour
+                .iter()
+                .zip(contour.iter().cycle().skip(1))
+                .map(|(a, b)| pts[a].0 * pts[b].1 - pts[a].1 * pts[b].0)
+                .sum::<f64>();
+            if area < 0.0 {
+                holes.push(contour);
+            } else if outer.replace(contour).is_some() {
+                return None;
+            }
+        }
+        let mut contour = bridge_holes(outer?, holes, &pts)?;
+        let points = contour
+            .iter()
+            .map(|pt| self.vertices.get_point(*pt))
+            .collect_vec();
+        contour.rotate_left(convex_corner(&points, plane.normal(), self.tolerance())?);
+        Some(contour)
+    }
+}
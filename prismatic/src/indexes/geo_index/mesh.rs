@@ -1,18 +1,36 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::Write,
+};
 
 use itertools::Itertools;
-use math::Scalar;
+use math::{CrossProduct, Scalar};
+use num_traits::Zero;
 
 use math::Vector3;
 
+use crate::{
+    indexes::{aabb::Aabb, vertex_index::PtId},
+    io::{obj, stl, IndexedPolygon},
+};
+
 use super::{
     face::FaceId,
     geo_object::{GeoObject, UnRef},
     index::GeoIndex,
     poly::{Poly, PolyId, UnrefPoly},
+    seg::SegmentDir,
+    transaction::MapMut,
 };
 
+/// Triangle of polygon together with polygon normal.
+pub(crate) type OrientedTriangle<S> = (Vector3<S>, [Vector3<S>; 3]);
+
+/// Points of mesh and triangles as indices in this point list.
+pub(crate) type IndexedTriangles<S> = (Vec<Vector3<S>>, Vec<[usize; 3]>);
+
 #[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mesh {
     poly_counter: usize,
     pub(super) polies: HashMap<PolyId, Poly>,
@@ -28,6 +46,7 @@ impl Mesh {
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MeshId(pub usize);
 
 impl PartialEq<usize> for MeshId {
@@ -69,6 +88,10 @@ impl<'a, S: Scalar> MeshRef<'a, S> {
             .collect()
     }
 
+    pub fn mesh_id(&self) -> MeshId {
+        self.mesh_id
+    }
+
     pub fn back_of(self, mesh_ref: MeshRef<'a, S>) -> Vec<UnrefPoly> {
         self.geo_index.select_polygons(
             self.mesh_id,
@@ -103,6 +126,211 @@ impl<'a, S: Scalar> MeshRef<'a, S> {
             .map(|p| (p.make_ref(self.geo_index).face_id(), p))
             .collect()
     }
+
+    /// Triangles of all polygons of mesh, together with normals of polygons.
+    /// Triangles are oriented same way, as polygons they are made of.
+    pub(crate) fn triangles(&self) -> anyhow::Result<Vec<OrientedTriangle<S>>> {
+        Ok(self
+            .pt_triangles()?
+            .into_iter()
+            .map(|(normal, triangle)| {
+                (
+                    normal,
+                    triangle.map(|pt| self.geo_index.vertices.get_point(pt)),
+                )
+            })
+            .collect())
+    }
+
+    /// Same as `triangles`, but vertices are ids of points in index.
+    fn pt_triangles(&self) -> anyhow::Result<Vec<(Vector3<S>, [PtId; 3])>> {
+        let mut triangles = Vec::new();
+        for poly in self.all_polygons() {
+            let poly = poly.make_ref(self.geo_index);
+            let normal = poly.normal();
+            for [a, b, c] in poly.face_id().make_ref(self.geo_index).triangles()? {
+                let triangle = match poly.dir() {
+                    SegmentDir::Fow => [a, b, c],
+                    SegmentDir::Rev => [a, c, b],
+                };
+                triangles.push((normal, triangle));
+            }
+        }
+        Ok(triangles)
+    }
+
+    /// Triangles of all polygons of mesh, oriented same way, as polygons they are made of.
+    /// Faces are triangulated with their holes and with all points on their contours.
+    pub fn triangulated(&self) -> anyhow::Result<Vec<[Vector3<S>; 3]>> {
+        Ok(self
+            .triangles()?
+            .into_iter()
+            .map(|(_, triangle)| triangle)
+            .collect())
+    }
+
+    /// Volume of the mesh. Mesh is expected to be closed; volume is positive, when polygons
+    /// are facing outside.
+    pub fn volume(&self) -> S {
+        // Divergence theorem: each polygon adds its area multiplied by distance of its plane
+        // from the origin.
+        self.oriented_polygons()
+            .into_iter()
+            .map(|(normal, pts)| Self::polygon_area(normal, &pts) * normal.dot(&pts[0]))
+            .sum::<S>()
+            / S::from_value(3)
+    }
+
+    pub fn surface_area(&self) -> S {
+        self.oriented_polygons()
+            .into_iter()
+            .map(|(normal, pts)| Self::polygon_area(normal, &pts))
+            .sum()
+    }
+
+    /// Center of mass of the solid, bounded by mesh. Returns `None` for mesh without volume.
+    pub fn centroid(&self) -> Option<Vector3<S>> {
+        let bbox = self.bounding_box()?;
+        // Tetrahedrons are built from the center of bounding box to keep numbers small.
+        let center = bbox.min.lerp(&bbox.max, S::half());
+        let mut volume = S::zero();
+        let mut moment = Vector3::zero();
+        for (_, pts) in self.oriented_polygons() {
+            let a = pts[0] - center;
+            for (b, c) in pts.iter().skip(1).tuple_windows() {
+                let (b, c) = (*b - center, *c - center);
+                let tetra_volume = a.dot(&b.cross_product(&c)) / S::from_value(6);
+                volume += tetra_volume;
+                moment += (a + b + c) * (tetra_volume / S::from_value(4));
+            }
+        }
+        if volume.is_zero() {
+            None
+        } else {
+            Some(center + moment / volume)
+        }
+    }
+
+    /// Exact bounding box of points, used by mesh polygons. Returns `None` for empty mesh.
+    pub fn bounding_box(&self) -> Option<Aabb<S>> {
+        let mut points = self
+            .oriented_polygons()
+            .into_iter()
+            .flat_map(|(_, pts)| pts);
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (
+                Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        });
+        Some(Aabb { min, max })
+    }
+
+    /// Polygon normals with polygon vertices, both following polygon direction.
+    fn oriented_polygons(&self) -> Vec<(Vector3<S>, Vec<Vector3<S>>)> {
+        self.all_polygons()
+            .into_iter()
+            .map(|poly| {
+                let poly = poly.make_ref(self.geo_index);
+                (poly.normal(), poly.segments().map(|seg| seg.from()).collect())
+            })
+            .collect()
+    }
+
+    /// Area of planar polygon. Works for non-convex polygons and polygons with holes, connected
+    /// by bridges, because bridge edges cancel each other.
+    fn polygon_area(normal: Vector3<S>, pts: &[Vector3<S>]) -> S {
+        let doubled: Vector3<S> = pts
+            .iter()
+            .skip(1)
+            .tuple_windows()
+            .map(|(b, c)| (*b - pts[0]).cross_product(&(*c - pts[0])))
+            .sum();
+        doubled.dot(&normal) / S::two()
+    }
+
+    /// Points, used by polygons of the mesh, and polygons as indices in this point list.
+    /// Polygon vertices follow polygon orientation.
+    pub(crate) fn compact_polygons(&self) -> (Vec<Vector3<S>>, Vec<IndexedPolygon>) {
+        let mut pt_ix = HashMap::new();
+        let mut points = Vec::new();
+        let polygons = self
+            .all_polygons()
+            .into_iter()
+            .map(|poly| {
+                let poly = poly.make_ref(self.geo_index);
+                let ixs = poly
+                    .segments()
+                    .map(|seg| {
+                        let pt = seg.from_pt();
+                        *pt_ix.entry(pt).or_insert_with(|| {
+                            points.push(self.geo_index.vertices.get_point(pt));
+                            points.len() - 1
+                        })
+                    })
+                    .collect_vec();
+                IndexedPolygon {
+                    mesh_id: self.mesh_id,
+                    face_id: poly.face_id(),
+                    vertices: ixs,
+                }
+            })
+            .collect_vec();
+        (points, polygons)
+    }
+
+    /// Points, used by polygons of the mesh, and triangles of polygons as indices in this point
+    /// list. Triangles are oriented same way, as polygons they are made of.
+    pub(crate) fn indexed_triangles(&self) -> anyhow::Result<IndexedTriangles<S>> {
+        let mut pt_ix = HashMap::new();
+        let mut points = Vec::new();
+        let triangles = self
+            .pt_triangles()?
+            .into_iter()
+            .map(|(_, triangle)| {
+                triangle.map(|pt| {
+                    *pt_ix.entry(pt).or_insert_with(|| {
+                        points.push(self.geo_index.vertices.get_point(pt));
+                        points.len() - 1
+                    })
+                })
+            })
+            .collect_vec();
+        Ok((points, triangles))
+    }
+
+    /// OpenSCAD polyhedron of this mesh. Only points, used by polygons of the mesh are written.
+    pub fn scad(&self) -> String {
+        let (points, polygons) = self.compact_polygons();
+        // OpenSCAD expects faces in clockwise order, when looking from outside.
+        let faces = polygons
+            .into_iter()
+            .map(|p| format!("[{}]", p.vertices.into_iter().rev().join(", ")))
+            .join(", \n");
+        let points = points
+            .into_iter()
+            .map(|v| format!("[{}, {}, {}]", v.x, v.y, v.z))
+            .join(", \n");
+
+        format!("polyhedron(points = [{points}], faces = [{faces}]);")
+    }
+
+    /// Writes mesh as Wavefront OBJ object. Polygons are written as is, without triangulation.
+    pub fn write_obj<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        obj::write_object(writer, self, 0)?;
+        Ok(())
+    }
+
+    /// Writes mesh as STL. Polygons with more than three vertices are triangulated.
+    pub fn write_stl<W: Write>(&self, writer: &mut W, binary: bool) -> anyhow::Result<()> {
+        let triangles = self.triangles()?;
+        if binary {
+            stl::write_binary(writer, &triangles)
+        } else {
+            stl::write_ascii(writer, &format!("mesh_{}", self.mesh_id.0), &triangles)
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -142,11 +370,86 @@ impl<'a, S: Scalar> MeshRefMut<'a, S> {
         )
     }
 
+    /// Union of this mesh with other. Both meshes are consumed by new one.
+    pub fn union(self, other: MeshId) -> anyhow::Result<MeshId> {
+        self.geo_index.union(self.mesh_id, other)
+    }
+
+    /// Subtracts other mesh from this one. Both meshes are consumed by new one.
+    pub fn difference(self, other: MeshId) -> anyhow::Result<MeshId> {
+        self.geo_index.difference(self.mesh_id, other)
+    }
+
+    /// Intersection of this mesh with other. Both meshes are consumed by new one.
+    pub fn intersection(self, other: MeshId) -> anyhow::Result<MeshId> {
+        self.geo_index.intersection(self.mesh_id, other)
+    }
+
+    /// Moves each group of connected polygons to its own new mesh. Polygons are connected, when
+    /// they share a rib. This mesh is removed.
+    pub fn split_components(mut self) -> Vec<MeshId> {
+        let mesh_id = self.mesh_id;
+        let polies = self.all_polygons();
+        let mut face_polies: HashMap<FaceId, Vec<PolyId>> = HashMap::new();
+        for poly in &polies {
+            let face_id = poly.make_ref(self.geo_index).face_id();
+            face_polies.entry(face_id).or_default().push(poly.poly_id);
+        }
+
+        let mut visited = HashSet::new();
+        let mut components = Vec::new();
+        for start in polies.iter().map(|p| p.poly_id).sorted() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(poly_id) = queue.pop_front() {
+                let adjacent = UnrefPoly { mesh_id, poly_id }
+                    .make_ref(self.geo_index)
+                    .segments()
+                    .flat_map(|seg| {
+                        self.geo_index
+                            .rib_to_face
+                            .get(&seg.rib_id())
+                            .into_iter()
+                            .flatten()
+                    })
+                    .filter_map(|face_id| face_polies.get(face_id))
+                    .flatten()
+                    .copied()
+                    .collect_vec();
+                for poly_id in adjacent {
+                    if visited.insert(poly_id) {
+                        component.push(poly_id);
+                        queue.push_back(poly_id);
+                    }
+                }
+            }
+            components.push(component);
+        }
+
+        let new_meshes = components
+            .into_iter()
+            .map(|component| {
+                let new_mesh = self.geo_index.new_mesh();
+                for poly_id in component {
+                    self.geo_index
+                        .move_polygon(UnrefPoly { mesh_id, poly_id }, new_mesh);
+                }
+                new_mesh
+            })
+            .collect_vec();
+        self.geo_index.meshes.remove(&mesh_id);
+
+        new_meshes
+    }
+
     fn mesh_obj(&self) -> &Mesh {
         &self.geo_index.meshes[&self.mesh_id]
     }
 
-    fn all_polygons(&mut self) -> Vec<UnrefPoly> {
+    pub(super) fn all_polygons(&mut self) -> Vec<UnrefPoly> {
         let items = self.mesh_obj().polies.keys().copied().collect_vec();
         let mesh_id = self.mesh_id;
         items
@@ -202,3 +505,110 @@ impl<'a, S: Scalar> UnRef<'a, S> for MeshRefMut<'a, S> {
         self.mesh_id
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use math::Vector3;
+
+    use crate::indexes::{
+        aabb::Aabb,
+        geo_index::{geo_object::GeoObject, index::GeoIndex, mesh::MeshId},
+    };
+
+    /// Faces of axis-aligned cube, looking outside.
+    pub(crate) fn cube_quads(center: Vector3<f64>, size: f64) -> [[Vector3<f64>; 4]; 6] {
+        let h = size / 2.0;
+        let v = |x: f64, y: f64, z: f64| center + Vector3::new(x * h, y * h, z * h);
+        [
+            [v(-1., -1., -1.), v(-1., 1., -1.), v(1., 1., -1.), v(1., -1., -1.)],
+            [v(-1., -1., 1.), v(1., -1., 1.), v(1., 1., 1.), v(-1., 1., 1.)],
+            [v(-1., -1., -1.), v(1., -1., -1.), v(1., -1., 1.), v(-1., -1., 1.)],
+            [v(-1., 1., -1.), v(-1., 1., 1.), v(1., 1., 1.), v(1., 1., -1.)],
+            [v(-1., -1., -1.), v(-1., -1., 1.), v(-1., 1., 1.), v(-1., 1., -1.)],
+            [v(1., -1., -1.), v(1., 1., -1.), v(1., 1., 1.), v(1., -1., 1.)],
+        ]
+    }
+
+    pub(crate) fn cube(index: &mut GeoIndex<f64>, center: Vector3<f64>, size: f64) -> MeshId {
+        let mesh_id = index.new_mesh();
+        for quad in cube_quads(center, size) {
+            mesh_id.make_mut_ref(index).add_polygon(&quad).unwrap();
+        }
+        mesh_id
+    }
+
+    #[test]
+    fn difference_reduces_volume() {
+        let mut index = GeoIndex::new(Aabb::from_points(&[
+            Vector3::new(-10.0, -10.0, -10.0),
+            Vector3::new(10.0, 10.0, 10.0),
+        ]))
+        .points_precision(0.001);
+
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        assert!((a.make_ref(&index).volume() - 8.0).abs() < 1e-9);
+        assert!((a.make_ref(&index).surface_area() - 24.0).abs() < 1e-9);
+
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+        let result = index.difference(a, b).unwrap();
+        let mesh = result.make_ref(&index);
+
+        assert!((mesh.volume() - 7.875).abs() < 1e-9);
+        assert!((mesh.surface_area() - 24.0).abs() < 1e-9);
+        let centroid = mesh.centroid().unwrap();
+        let expected = -0.75 * 0.125 / 7.875;
+        assert!((centroid - Vector3::new(expected, expected, expected)).magnitude() < 1e-9);
+    }
+
+    pub(crate) fn new_index() -> GeoIndex<f64> {
+        GeoIndex::new(Aabb::from_points(&[
+            Vector3::new(-10.0, -10.0, -10.0),
+            Vector3::new(10.0, 10.0, 10.0),
+        ]))
+        .points_precision(0.001)
+    }
+
+    #[test]
+    fn scad_of_each_mesh() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+
+        let scad = a.make_ref(&index).scad();
+        let (points, faces) = scad.split_once("faces = ").unwrap();
+        assert_eq!(points.matches('[').count() - 1, 8);
+        assert_eq!(faces.matches('[').count() - 1, 6);
+
+        let multi = index.scad_multi();
+        assert_eq!(multi.matches("module mesh_").count(), 2);
+        assert_eq!(multi.matches("polyhedron(").count(), 2);
+    }
+
+    #[test]
+    fn split_disjoint_cubes() {
+        let mut index = new_index();
+        let mesh_id = index.new_mesh();
+        let quads = cube_quads(Vector3::new(0.0, 0.0, 0.0), 2.0)
+            .into_iter()
+            .chain(cube_quads(Vector3::new(4.0, 0.0, 0.0), 1.0));
+        for quad in quads {
+            mesh_id.make_mut_ref(&mut index).add_polygon(&quad).unwrap();
+        }
+
+        let parts = mesh_id.make_mut_ref(&mut index).split_components();
+        let mut volumes = parts
+            .iter()
+            .map(|part| {
+                let mesh = part.make_ref(&index);
+                assert_eq!(mesh.all_polygons().len(), 6);
+                assert!(mesh.validate().is_valid());
+                mesh.volume()
+            })
+            .collect::<Vec<_>>();
+        volumes.sort_by(f64::total_cmp);
+        assert_eq!(volumes.len(), 2);
+        assert!((volumes[0] - 1.0).abs() < 1e-9);
+        assert!((volumes[1] - 8.0).abs() < 1e-9);
+        assert!(!index.meshes.contains_key(&mesh_id));
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use math::{CrossProduct, Scalar};
+
+use super::{
+    containment::Containment,
+    geo_object::GeoObject,
+    mesh::MeshRefMut,
+    poly::{PolyId, UnrefPoly},
+    rib::RibId,
+    seg::SegmentDir,
+};
+
+impl<S: Scalar> MeshRefMut<'_, S> {
+    /// Flips polygons, so that adjacent polygons go through common rib in opposite directions,
+    /// and every connected component of the mesh has positive volume - unless it is a cavity
+    /// inside of other component, then its volume is negative.
+    /// Ribs, shared by more than two polygons, do not connect polygons here - there is no
+    /// single right orientation for them.
+    /// Returns number of flipped polygons.
+    pub fn orient_consistently(&mut self) -> usize {
+        let mesh_id = self.mesh_id;
+        let polies = self.all_polygons();
+
+        let mut poly_ribs: HashMap<PolyId, Vec<(RibId, SegmentDir)>> = HashMap::new();
+        let mut rib_polies: BTreeMap<RibId, Vec<PolyId>> = BTreeMap::new();
+        for poly in &polies {
+            // Bridges are used twice by same polygon, they do not connect it to others.
+            let ribs = poly
+                .make_ref(self.geo_index)
+                .non_bridge_segments()
+                .into_iter()
+                .map(|s| (s.rib_id(), s.dir))
+                .collect_vec();
+            for (rib_id, _) in &ribs {
+                rib_polies.entry(*rib_id).or_default().push(poly.poly_id);
+            }
+            poly_ribs.insert(poly.poly_id, ribs);
+        }
+
+        let mut flips: BTreeMap<PolyId, bool> = BTreeMap::new();
+        let mut components = Vec::new();
+        for start in polies.iter().map(|p| p.poly_id).sorted() {
+            if flips.contains_key(&start) {
+                continue;
+            }
+            flips.insert(start, false);
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(poly_id) = queue.pop_front() {
+                let flip = flips[&poly_id];
+                for (rib_id, dir) in &poly_ribs[&poly_id] {
+                    let [a, b] = rib_polies[rib_id].as_slice() else {
+                        continue;
+                    };
+                    let other = if *a == poly_id { *b } else { *a };
+                    if flips.contains_key(&other) {
+                        continue;
+                    }
+                    let dir = if flip { dir.flip() } else { *dir };
+                    let other_dir = poly_ribs[&other]
+                        .iter()
+                        .find(|(r, _)| r == rib_id)
+                        .map(|(_, d)| *d)
+                        .expect("Rib is listed for polygon");
+                    flips.insert(other, other_dir == dir);
+                    component.push(other);
+                    queue.push_back(other);
+                }
+            }
+            components.push(component);
+        }
+
+        // Shell inside of odd number of other shells bounds a cavity, so it has negative volume.
+        let shells = components
+            .iter()
+            .map(|component| {
+                component
+                    .iter()
+                    .map(|poly_id| self.geo_index.load_polygon_ref(mesh_id, *poly_id).face_id())
+                    .collect::<HashSet<_>>()
+            })
+            .collect_vec();
+        let mesh = mesh_id.make_ref(self.geo_index);
+        let depths = shells
+            .iter()
+            .enumerate()
+            .map(|(ix, shell)| {
+                let face_id = shell.iter().next().expect("Shell has faces");
+                let pt = face_id
+                    .make_ref(self.geo_index)
+                    .segments(SegmentDir::Fow)
+                    .next()
+                    .expect("Face has segments")
+                    .from();
+                shells
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_ix, other)| {
+                        *other_ix != ix
+                            && mesh.faces_contain_point(pt, other) == Containment::Inside
+                    })
+                    .count()
+            })
+            .collect_vec();
+
+        let mut flipped = 0;
+        for (component, depth) in components.into_iter().zip(depths) {
+            let negative = self.signed_volume(&component, &flips).is_sign_negative();
+            let flip_all = negative != (depth % 2 == 1);
+            for poly_id in component {
+                if flips[&poly_id] != flip_all {
+                    UnrefPoly { mesh_id, poly_id }
+                        .make_mut_ref(self.geo_index)
+                        .flip();
+                    flipped += 1;
+                }
+            }
+        }
+        flipped
+    }
+
+    /// Volume of polygons, as if some of them were flipped.
+    fn signed_volume(&self, polies: &[PolyId], flips: &BTreeMap<PolyId, bool>) -> S {
+        let mesh_id = self.mesh_id;
+        polies
+            .iter()
+            .map(|poly_id| {
+                let pts = UnrefPoly {
+                    mesh_id,
+                    poly_id: *poly_id,
+                }
+                .make_ref(self.geo_index)
+                .segments()
+                .map(|s| s.from())
+                .collect_vec();
+                let volume = pts
+                    .iter()
+                    .skip(1)
+                    .tuple_windows()
+                    .map(|(b, c)| pts[0].dot(&b.cross_product(c)))
+                    .fold(S::zero(), |acc, v| acc + v);
+                if flips[poly_id] {
+                    -volume
+                } else {
+                    volume
+                }
+            })
+            .fold(S::zero(), |acc, v| acc + v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        containment::Containment,
+        geo_object::GeoObject,
+        mesh::tests::{cube, cube_quads, new_index},
+    };
+
+    #[test]
+    fn orient_inverted_cube() {
+        let mut index = new_index();
+        let mesh_id = index.new_mesh();
+        let quads = cube_quads(Vector3::new(0.0, 0.0, 0.0), 2.0);
+        for (ix, mut quad) in quads.into_iter().enumerate() {
+            if ix != 0 {
+                quad.reverse();
+            }
+            mesh_id.make_mut_ref(&mut index).add_polygon(&quad).unwrap();
+        }
+        assert!(!mesh_id.make_ref(&index).validate().is_valid());
+
+        assert_eq!(mesh_id.make_mut_ref(&mut index).orient_consistently(), 5);
+        let mesh = mesh_id.make_ref(&index);
+        assert!((mesh.volume() - 8.0).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+    }
+
+    #[test]
+    fn orient_hollow_cube() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 4.0);
+        let b = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mesh_id = index.difference(a, b).unwrap();
+        assert!((mesh_id.make_ref(&index).volume() - 63.0).abs() < 1e-9);
+
+        // Cavity keeps looking inside.
+        assert_eq!(mesh_id.make_mut_ref(&mut index).orient_consistently(), 0);
+        let mesh = mesh_id.make_ref(&index);
+        assert!((mesh.volume() - 63.0).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+        let wall = Vector3::new(1.5, 0.0, 0.0);
+        assert_eq!(mesh.contains_point(wall), Containment::Inside);
+    }
+
+    #[test]
+    fn orient_hollow_cube_with_inverted_polygon() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 4.0);
+        let b = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mesh_id = index.difference(a, b).unwrap();
+        let cavity_poly = mesh_id
+            .make_ref(&index)
+            .all_polygons()
+            .into_iter()
+            .find(|poly| {
+                let pt = poly.make_ref(&index).segments().next().unwrap().from();
+                pt.magnitude() < 1.0
+            })
+            .unwrap();
+        cavity_poly.make_mut_ref(&mut index).flip();
+        assert!(!mesh_id.make_ref(&index).validate().is_valid());
+
+        assert_eq!(mesh_id.make_mut_ref(&mut index).orient_consistently(), 1);
+        let mesh = mesh_id.make_ref(&index);
+        assert!((mesh.volume() - 63.0).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+    }
+}
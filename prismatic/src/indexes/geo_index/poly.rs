@@ -15,6 +15,7 @@ use super::{
     index::GeoIndex,
     mesh::MeshId,
     seg::{SegRef, SegmentDir},
+    transaction::MapMut,
 };
 
 #[derive(PartialEq, Eq, Clone, Debug, Hash, Copy)]
@@ -30,6 +31,22 @@ impl<'a, S: Scalar> PolyRef<'a, S> {
         face.segments(poly.dir())
     }
 
+    /// Segments, which go in direction of polygon: for reversed polygon they are flipped.
+    pub(crate) fn oriented_segments(&self) -> impl Iterator<Item = SegRef<'a, S>> + 'a {
+        let dir = self.dir();
+        self.segments()
+            .map(move |s| if dir == SegmentDir::Rev { s.flip() } else { s })
+    }
+
+    /// Oriented segments of polygon without bridges of its face. See
+    /// `FaceRef::non_bridge_segments`.
+    pub(crate) fn non_bridge_segments(&self) -> Vec<SegRef<'a, S>> {
+        let counts = self.segments().map(|s| s.rib_id()).counts();
+        self.oriented_segments()
+            .filter(|s| counts[&s.rib_id()] == 1)
+            .collect()
+    }
+
     pub fn svg_debug(&self, vertices: Vec<Vector2<S>>) -> String
     where
         S: fmt::Display,
@@ -308,6 +325,7 @@ impl<S: Scalar> fmt::Debug for PolyRef<'_, S> {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Poly {
     pub(crate) face_id: FaceId,
     pub(crate) dir: SegmentDir,
@@ -334,6 +352,7 @@ impl Poly {
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PolyId(pub usize);
 
 impl PartialEq<usize> for PolyId {
@@ -1,4 +1,4 @@
-use rstar::{Point, RTreeObject, AABB};
+use rstar::{Point, RTreeObject, SelectionFunction, AABB};
 
 use crate::indexes::aabb::Aabb;
 use math::{Scalar, Vector3};
@@ -42,3 +42,48 @@ pub struct RtreePt<S>([S; 3]);
 
 #[derive(Debug, PartialEq)]
 pub struct FaceRtreeRecord<S>(pub(super) FaceId, pub(super) Aabb<S>);
+
+/// Selects faces, which bounding boxes are crossed by ray.
+pub(super) struct RaySelection<S> {
+    pub(super) origin: Vector3<S>,
+    pub(super) dir: Vector3<S>,
+    /// Boxes are enlarged by this value, so ray, touching box, selects it.
+    pub(super) tolerance: S,
+}
+
+impl<S: Scalar> RaySelection<S> {
+    fn crosses(&self, envelope: &AABB<RtreePt<S>>) -> bool {
+        let (lower, upper) = (envelope.lower(), envelope.upper());
+        let mut enter = S::zero();
+        let mut exit = <S as num_traits::Bounded>::max_value();
+        let (origin, dir) = (RtreePt::from(self.origin), RtreePt::from(self.dir));
+        for axis in 0..3 {
+            let min = lower.nth(axis) - self.tolerance;
+            let max = upper.nth(axis) + self.tolerance;
+            let (origin, dir) = (origin.nth(axis), dir.nth(axis));
+            if dir.is_zero() {
+                if origin < min || origin > max {
+                    return false;
+                }
+                continue;
+            }
+            let (a, b) = ((min - origin) / dir, (max - origin) / dir);
+            enter = enter.max(a.min(b));
+            exit = exit.min(a.max(b));
+            if enter > exit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<S: Scalar> SelectionFunction<FaceRtreeRecord<S>> for RaySelection<S> {
+    fn should_unpack_parent(&self, envelope: &AABB<RtreePt<S>>) -> bool {
+        self.crosses(envelope)
+    }
+
+    fn should_unpack_leaf(&self, leaf: &FaceRtreeRecord<S>) -> bool {
+        self.crosses(&leaf.envelope())
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use math::{CrossProduct, Scalar, Vector3};
+
+use crate::{
+    linear::{line::Line, ray::Ray},
+    primitives_relation::{linear_point::PointOnLine, relation::Relation},
+};
+
+use super::{
+    face::FaceId, geo_object::GeoObject, index::GeoIndex, mesh::MeshId, poly::UnrefPoly,
+    poly_rtree::RaySelection, seg::SegmentDir,
+};
+
+/// Point, where ray crosses polygon of mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit<S> {
+    /// Distance from ray origin to the point.
+    pub distance: S,
+    pub face_id: FaceId,
+    pub mesh_id: MeshId,
+    pub point: Vector3<S>,
+    /// Normal of polygon, which is hit. It follows polygon direction in this mesh.
+    pub normal: Vector3<S>,
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// All crossings of ray with polygons, sorted by distance from ray origin. When `filter` is set,
+    /// only polygons of this mesh are checked.
+    /// Face, shared by several meshes, gives hit for each of them. Ray, which passes through rib,
+    /// hits every face around the rib. Faces, parallel to ray, are never hit.
+    pub fn raycast(&self, ray: Ray<S>, filter: Option<MeshId>) -> Vec<Hit<S>> {
+        let dir = ray.dir.normalize();
+        let ray = Ray {
+            origin: ray.origin,
+            dir,
+        };
+        let selection = RaySelection {
+            origin: ray.origin,
+            dir,
+            tolerance: self.tolerance(),
+        };
+
+        let face_hits: BTreeMap<FaceId, (S, Vector3<S>)> = self
+            .face_index
+            .locate_with_selection_function(selection)
+            .filter_map(|record| {
+                let face_id = record.0;
+                let distance = self.faces[&face_id]
+                    .plane()
+                    .get_intersection_param2(ray.origin, ray.origin + dir)?;
+                let point = ray.origin + dir * distance;
+                let ahead = matches!(ray.relate(&point), PointOnLine::On | PointOnLine::Origin);
+                (ahead && self.face_contains_point(face_id, point))
+                    .then_some((face_id, (distance, point)))
+            })
+            .collect();
+
+        self.meshes
+            .iter()
+            .filter(|(mesh_id, _)| filter.is_none_or(|m| m == **mesh_id))
+            .flat_map(|(mesh_id, mesh)| {
+                mesh.polies.iter().filter_map(|(poly_id, poly)| {
+                    let (distance, point) = face_hits.get(&poly.face_id)?;
+                    let normal = UnrefPoly {
+                        mesh_id: *mesh_id,
+                        poly_id: *poly_id,
+                    }
+                    .make_ref(self)
+                    .normal();
+                    Some(Hit {
+                        distance: *distance,
+                        face_id: poly.face_id,
+                        mesh_id: *mesh_id,
+                        point: *point,
+                        normal,
+                    })
+                })
+            })
+            .sorted_by(|a, b| {
+                a.distance
+                    .partial_cmp(&b.distance)
+                    .expect("Distances are comparable")
+                    .then(a.mesh_id.cmp(&b.mesh_id))
+                    .then(a.face_id.cmp(&b.face_id))
+            })
+            .collect()
+    }
+
+    /// Nearest crossing of ray with polygons. See `raycast`.
+    pub fn first_hit(&self, ray: Ray<S>, filter: Option<MeshId>) -> Option<Hit<S>> {
+        self.raycast(ray, filter).into_iter().next()
+    }
+
+    /// Checks, that point in plane of face is inside of it or on its contour. Line in plane of face
+    /// is drawn from the point, and its crossings with face contour are counted.
+    pub(super) fn face_contains_point(&self, face_id: FaceId, point: Vector3<S>) -> bool {
+        let vertex_pulling = self.tolerance();
+        let vertex_pulling_sq = vertex_pulling * vertex_pulling;
+        let face = face_id.make_ref(self);
+        let segs = face.segments(SegmentDir::Fow).collect_vec();
+        let Some(seg) = segs.first() else {
+            return false;
+        };
+        if segs
+            .iter()
+            .any(|s| distance_to_segment_sq(point, s.from(), s.to()) < vertex_pulling_sq)
+        {
+            return true;
+        }
+        let line = Line {
+            origin: point,
+            dir: seg.dir().cross_product(&face.plane().normal()).normalize(),
+        };
+        self.collect_line_face_intersections(line, face_id, vertex_pulling_sq, false) % 2 == 1
+    }
+}
+
+pub(super) fn distance_to_segment_sq<S: Scalar>(
+    pt: Vector3<S>,
+    from: Vector3<S>,
+    to: Vector3<S>,
+) -> S {
+    (pt - closest_point_on_segment(pt, from, to)).magnitude_squared()
+}
+
+pub(super) fn closest_point_on_segment<S: Scalar>(
+    pt: Vector3<S>,
+    from: Vector3<S>,
+    to: Vector3<S>,
+) -> Vector3<S> {
+    let dir = to - from;
+    let t = (pt - from).dot(&dir) / dir.magnitude_squared();
+    let t = t.max(S::zero()).min(S::one());
+    from + dir * t
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::{
+        indexes::geo_index::mesh::tests::{cube, new_index},
+        linear::ray::Ray,
+    };
+
+    #[test]
+    fn first_hit_of_ray() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+        // Direction is not normalized on purpose.
+        let along_x = |x: f64, y: f64, z: f64| Ray {
+            origin: Vector3::new(x, y, z),
+            dir: Vector3::new(2.0, 0.0, 0.0),
+        };
+
+        let hit = index.first_hit(along_x(-5.0, 0.2, 0.3), None).unwrap();
+        assert_eq!(hit.mesh_id, a);
+        assert!((hit.distance - 4.0).abs() < 1e-9);
+        assert!((hit.point - Vector3::new(-1.0, 0.2, 0.3)).magnitude() < 1e-9);
+        assert!((hit.normal - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-9);
+        assert!(index.face_contains_point(hit.face_id, hit.point));
+
+        assert_eq!(index.raycast(along_x(-5.0, 0.2, 0.3), None).len(), 4);
+        let hit = index.first_hit(along_x(-5.0, 0.2, 0.3), Some(b)).unwrap();
+        assert!((hit.distance - 8.5).abs() < 1e-9);
+
+        let hit = index.first_hit(along_x(0.0, 0.0, 0.0), None).unwrap();
+        assert_eq!(hit.mesh_id, a);
+        assert!((hit.distance - 1.0).abs() < 1e-9);
+        assert!((hit.normal - Vector3::new(1.0, 0.0, 0.0)).magnitude() < 1e-9);
+
+        assert!(index.first_hit(along_x(-5.0, 3.0, 0.0), None).is_none());
+        assert!(index.first_hit(along_x(5.0, 0.0, 0.0), None).is_none());
+    }
+}
@@ -10,9 +10,11 @@ use super::{
 };
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RibId(pub(super) usize);
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rib(pub(super) PtId, pub(super) PtId);
 
 impl Rib {
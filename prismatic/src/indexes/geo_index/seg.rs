@@ -18,6 +18,7 @@ use super::{
 pub struct SegId(Uuid);
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SegmentDir {
     Fow,
     Rev,
@@ -33,6 +34,7 @@ impl SegmentDir {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Seg {
     pub(super) rib_id: RibId,
     pub(super) dir: SegmentDir,
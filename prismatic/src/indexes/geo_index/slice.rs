@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, HashMap};
+
+use itertools::Itertools;
+use math::{CrossProduct, Scalar, Vector2, Vector3};
+
+use crate::{indexes::vertex_index::PtId, planar::plane::Plane, polygon_basis::PolygonBasis};
+
+use super::{geo_object::GeoObject, index::GeoIndex, mesh::MeshId, rib::RibId, seg::SegmentDir};
+
+/// Point, where plane cuts mesh: either vertex of mesh, lying on plane, or point inside rib.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+enum CutPoint {
+    Vertex(PtId),
+    Rib(RibId),
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Contours, where plane cuts polygons of mesh. Outer contours go counter-clockwise around
+    /// plane normal, contours of holes go clockwise. Vertices, lying on plane, are treated as if
+    /// they were slightly in front of it, so polygons in plane itself are not cut. Polygons in
+    /// plane, which look back, are added to section instead, so plane through face of mesh gives
+    /// contour of this face, whichever way the face looks.
+    /// Closed mesh gives closed contours; last point of contour is not repeated.
+    pub fn slice(&self, mesh_id: MeshId, plane: Plane<S>) -> Vec<Vec<Vector3<S>>> {
+        let tolerance = self.tolerance();
+        let distance = |pt: PtId| plane.normal().dot(&self.vertices.get_point(pt)) - plane.d();
+        let in_front = |d: S| d > -tolerance;
+
+        let mut points: HashMap<CutPoint, Vector3<S>> = HashMap::new();
+        let mut cuts: BTreeMap<CutPoint, Vec<CutPoint>> = BTreeMap::new();
+        for poly in mesh_id.make_ref(self).all_polygons() {
+            let poly = poly.make_ref(self);
+            // Cuts inside polygon go along this line, so that polygon normal looks outside.
+            let cut_dir = plane.normal().cross_product(&poly.normal());
+            if cut_dir.magnitude_squared() < tolerance * tolerance {
+                let segs = poly.non_bridge_segments();
+                let looks_back = poly.normal().dot(&plane.normal()).is_negative();
+                if looks_back && segs.iter().all(|s| distance(s.from_pt()).abs() < tolerance) {
+                    // Polygon goes clockwise around plane normal, so its section goes backwards.
+                    for s in segs {
+                        let (from, to) =
+                            (CutPoint::Vertex(s.from_pt()), CutPoint::Vertex(s.to_pt()));
+                        points.insert(from, s.from());
+                        points.insert(to, s.to());
+                        cuts.entry(to).or_default().push(from);
+                    }
+                }
+                continue;
+            }
+
+            // Bridges are crossed twice and give nothing.
+            let crossings = poly
+                .face_id()
+                .make_ref(self)
+                .non_bridge_segments(SegmentDir::Fow)
+                .iter()
+                .filter_map(|s| {
+                    let (from, to) = (distance(s.from_pt()), distance(s.to_pt()));
+                    if in_front(from) == in_front(to) {
+                        None
+                    } else if from.abs() < tolerance {
+                        Some((CutPoint::Vertex(s.from_pt()), s.from()))
+                    } else if to.abs() < tolerance {
+                        Some((CutPoint::Vertex(s.to_pt()), s.to()))
+                    } else {
+                        let pt = self.rib_cut_point(s.rib_id(), &plane);
+                        Some((CutPoint::Rib(s.rib_id()), pt))
+                    }
+                })
+                .sorted_by(|(_, a), (_, b)| {
+                    cut_dir
+                        .dot(a)
+                        .partial_cmp(&cut_dir.dot(b))
+                        .expect("Points are comparable")
+                })
+                .collect_vec();
+
+            // Polygon is inside between first and second crossing, third and fourth, and so on.
+            for ((from, from_pt), (to, to_pt)) in crossings.into_iter().tuples() {
+                if from != to {
+                    points.insert(from, from_pt);
+                    points.insert(to, to_pt);
+                    cuts.entry(from).or_default().push(to);
+                }
+            }
+        }
+
+        // Polygon in plane shares its contour with section, going behind it, in opposite direction.
+        let opposite = cuts
+            .iter()
+            .flat_map(|(from, to)| to.iter().map(move |to| (*from, *to)))
+            .filter(|(from, to)| cuts.get(to).is_some_and(|back| back.contains(from)))
+            .collect_vec();
+        for (from, to) in opposite {
+            if let Some(to_pts) = cuts.get_mut(&from) {
+                to_pts.retain(|pt| *pt != to);
+            }
+        }
+        cuts.retain(|_, to| !to.is_empty());
+
+        chain_cuts(cuts)
+            .into_iter()
+            .map(|(contour, _)| contour.iter().map(|pt| points[pt]).collect())
+            .collect()
+    }
+
+    /// Point, where plane crosses rib. It is found from the first point of rib, so both faces
+    /// around the rib get the same point.
+    pub(super) fn rib_cut_point(&self, rib_id: RibId, plane: &Plane<S>) -> Vector3<S> {
+        let rib = self.ribs[&rib_id];
+        let (a, b) = (
+            self.vertices.get_point(rib.0),
+            self.vertices.get_point(rib.1),
+        );
+        let (da, db) = (
+            plane.normal().dot(&a) - plane.d(),
+            plane.normal().dot(&b) - plane.d(),
+        );
+        a.lerp(&b, da / (da - db))
+    }
+
+    /// Contours, where plane cuts mesh, in coordinates of plane. Returned basis converts them back
+    /// to 3d. See `slice`.
+    pub fn slice_2d(
+        &self,
+        mesh_id: MeshId,
+        plane: Plane<S>,
+    ) -> (PolygonBasis<S>, Vec<Vec<Vector2<S>>>) {
+        let basis = plane_basis(&plane);
+        let contours = self
+            .slice(mesh_id, plane)
+            .into_iter()
+            .map(|contour| {
+                contour
+                    .iter()
+                    .map(|pt| basis.project_on_plane_z(pt))
+                    .collect()
+            })
+            .collect();
+        (basis, contours)
+    }
+}
+
+/// Basis in plane, which is right-handed with plane normal.
+pub(super) fn plane_basis<S: Scalar>(plane: &Plane<S>) -> PolygonBasis<S> {
+    let normal = plane.normal();
+    // Axis, most perpendicular to normal, gives x direction of basis.
+    let axis = [Vector3::x(), Vector3::y(), Vector3::z()]
+        .into_iter()
+        .min_by(|a, b| {
+            normal
+                .dot(a)
+                .abs()
+                .partial_cmp(&normal.dot(b).abs())
+                .expect("Numbers are comparable")
+        })
+        .expect("Three axes");
+    let x = (axis - normal * normal.dot(&axis)).normalize();
+    PolygonBasis {
+        center: plane.point_on_plane(),
+        x,
+        y: normal.cross_product(&x).normalize(),
+    }
+}
+
+/// Joins cuts, going from one point to others, into contours. Each contour is returned with a flag,
+/// telling, that it is closed. Last point of closed contour is not repeated.
+pub(super) fn chain_cuts<K: Ord + Copy>(mut cuts: BTreeMap<K, Vec<K>>) -> Vec<(Vec<K>, bool)> {
+    let mut contours = Vec::new();
+    let starts = cuts.keys().copied().collect_vec();
+    for start in starts {
+        while let Some(mut next) = cuts.get_mut(&start).and_then(|n| n.pop()) {
+            let mut contour = vec![start];
+            let mut closed = true;
+            while next != start {
+                contour.push(next);
+                match cuts.get_mut(&next).and_then(|n| n.pop()) {
+                    Some(n) => next = n,
+                    // Mesh is not closed.
+                    None => {
+                        closed = false;
+                        break;
+                    }
+                }
+            }
+            contours.push((contour, closed));
+        }
+    }
+    contours
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::{
+        indexes::geo_index::mesh::tests::{cube, new_index},
+        planar::plane::Plane,
+    };
+
+    #[test]
+    fn slice_of_hollow_cube() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let hollow = index.difference(a, b).unwrap();
+
+        let plane = Plane::new_from_normal_and_point(
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, 0.2),
+        );
+        assert_eq!(index.slice(hollow, plane.clone()).len(), 2);
+        let (_, contours) = index.slice_2d(hollow, plane);
+        let mut areas = contours
+            .iter()
+            .map(|contour| {
+                let doubled: f64 = contour
+                    .iter()
+                    .zip(contour.iter().cycle().skip(1))
+                    .map(|(p, q)| p.x * q.y - q.x * p.y)
+                    .sum();
+                doubled / 2.0
+            })
+            .collect::<Vec<_>>();
+        areas.sort_by(f64::total_cmp);
+        // Outer contour goes counter-clockwise, contour of cavity goes clockwise.
+        assert!((areas[0] + 1.0).abs() < 1e-9);
+        assert!((areas[1] - 4.0).abs() < 1e-9);
+
+        let above = Plane::new_from_normal_and_point(
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, 3.0),
+        );
+        assert!(index.slice(hollow, above).is_empty());
+    }
+
+    #[test]
+    fn slice_through_faces_of_cube() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let hollow = index.difference(a, b).unwrap();
+
+        // Top and bottom faces of cube, then top and bottom faces of cavity, which fill the hole.
+        for z in [1.0, -1.0, 0.5, -0.5] {
+            let plane = Plane::new_from_normal_and_point(
+                Vector3::new(0.0, 0.0, 1.0),
+                Vector3::new(0.0, 0.0, z),
+            );
+            let (_, contours) = index.slice_2d(hollow, plane);
+            assert_eq!(contours.len(), 1, "z = {z}");
+            let contour = &contours[0];
+            let doubled: f64 = contour
+                .iter()
+                .zip(contour.iter().cycle().skip(1))
+                .map(|(p, q)| p.x * q.y - q.x * p.y)
+                .sum();
+            assert!((doubled / 2.0 - 4.0).abs() < 1e-9, "z = {z}");
+        }
+    }
+}
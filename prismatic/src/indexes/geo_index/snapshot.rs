@@ -0,0 +1,133 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+};
+
+use math::{Scalar, Vector3};
+use rstar::RTree;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::indexes::{
+    aabb::Aabb,
+    vertex_index::{PtId, VertexIndex},
+};
+
+use super::{
+    face::{Face, FaceId},
+    index::GeoIndex,
+    mesh::{Mesh, MeshId},
+    poly_rtree::FaceRtreeRecord,
+    rib::{Rib, RibId},
+};
+
+/// Everything, that index needs to continue work after loading. Spatial indexes (face rtree and
+/// vertex octree) are not stored - they are rebuilt from faces and points.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "S: Serialize", deserialize = "S: DeserializeOwned"))]
+struct Snapshot<'a, S: Scalar> {
+    aabb: Aabb<S>,
+    points: Cow<'a, [Vector3<S>]>,
+    ribs: Cow<'a, BTreeMap<RibId, Rib>>,
+    faces: Cow<'a, BTreeMap<FaceId, Face<S>>>,
+    meshes: Cow<'a, HashMap<MeshId, Mesh>>,
+    pt_to_ribs: Cow<'a, BTreeMap<PtId, Vec<RibId>>>,
+    rib_to_face: Cow<'a, BTreeMap<RibId, Vec<FaceId>>>,
+    partially_split_faces: Cow<'a, BTreeMap<FaceId, Vec<RibId>>>,
+    face_splits: Cow<'a, BTreeMap<FaceId, Vec<FaceId>>>,
+    rib_parent: Cow<'a, BTreeMap<RibId, RibId>>,
+    deleted_faces: Cow<'a, BTreeMap<FaceId, Face<S>>>,
+    split_ribs: Cow<'a, BTreeMap<RibId, Vec<RibId>>>,
+    input_polygon_min_rib_length: S,
+    points_precision: S,
+    rib_counter: usize,
+    face_counter: usize,
+    mesh_counter: usize,
+}
+
+impl<S: Scalar + Serialize> Serialize for GeoIndex<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        Snapshot {
+            aabb: self.vertices.aabb(),
+            points: Cow::Borrowed(self.vertices.points()),
+            ribs: Cow::Borrowed(&self.ribs),
+            faces: Cow::Borrowed(&self.faces),
+            meshes: Cow::Borrowed(&self.meshes),
+            pt_to_ribs: Cow::Borrowed(&self.pt_to_ribs),
+            rib_to_face: Cow::Borrowed(&self.rib_to_face),
+            partially_split_faces: Cow::Borrowed(&self.partially_split_faces),
+            face_splits: Cow::Borrowed(&self.face_splits),
+            rib_parent: Cow::Borrowed(&self.rib_parent),
+            deleted_faces: Cow::Borrowed(&self.deleted_faces),
+            split_ribs: Cow::Borrowed(&self.split_ribs),
+            input_polygon_min_rib_length: self.input_polygon_min_rib_length,
+            points_precision: self.points_precision,
+            rib_counter: self.rib_counter,
+            face_counter: self.face_counter,
+            mesh_counter: self.mesh_counter,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, S: Scalar + DeserializeOwned> Deserialize<'de> for GeoIndex<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = Snapshot::<S>::deserialize(deserializer)?;
+        let faces = snapshot.faces.into_owned();
+        let face_index = RTree::bulk_load(
+            faces
+                .iter()
+                .map(|(face_id, face)| FaceRtreeRecord(*face_id, *face.aabb()))
+                .collect(),
+        );
+
+        let mut index = GeoIndex::new(snapshot.aabb);
+        index.vertices = VertexIndex::from_points(snapshot.aabb, snapshot.points.into_owned());
+        index.face_index = face_index;
+        index.ribs = snapshot.ribs.into_owned().into();
+        index.faces = faces.into();
+        index.meshes = snapshot.meshes.into_owned().into();
+        index.pt_to_ribs = snapshot.pt_to_ribs.into_owned().into();
+        index.rib_to_face = snapshot.rib_to_face.into_owned().into();
+        index.partially_split_faces = snapshot.partially_split_faces.into_owned().into();
+        index.face_splits = snapshot.face_splits.into_owned().into();
+        index.rib_parent = snapshot.rib_parent.into_owned().into();
+        index.deleted_faces = snapshot.deleted_faces.into_owned().into();
+        index.split_ribs = snapshot.split_ribs.into_owned().into();
+        index.input_polygon_min_rib_length = snapshot.input_polygon_min_rib_length;
+        index.points_precision = snapshot.points_precision;
+        index.rib_counter = snapshot.rib_counter;
+        index.face_counter = snapshot.face_counter;
+        index.mesh_counter = snapshot.mesh_counter;
+
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        index::GeoIndex,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+        let result = index.difference(a, b).unwrap();
+
+        let json = serde_json::to_string(&index).unwrap();
+        let mut restored: GeoIndex<f64> = serde_json::from_str(&json).unwrap();
+        let mesh = result.make_ref(&restored);
+        assert!((mesh.volume() - 7.875).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+
+        let c = cube(&mut restored, Vector3::new(-1.0, -1.0, -1.0), 1.0);
+        let result = restored.difference(result, c).unwrap();
+        assert!((result.make_ref(&restored).volume() - 7.75).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,516 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::bail;
+use itertools::Itertools;
+use math::{CrossProduct, Scalar, Vector3};
+
+use crate::{indexes::vertex_index::PtId, planar::plane::Plane};
+
+use super::{
+    geo_object::GeoObject,
+    index::GeoIndex,
+    mesh::{MeshId, MeshRefMut},
+    poly::UnrefPoly,
+    rib::RibId,
+    slice::{chain_cuts, plane_basis},
+    transaction::MapMut,
+};
+
+pub(super) type Pt2 = (f64, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Back,
+    On,
+    Front,
+}
+
+/// Place, where contour of polygon crosses plane: run of contour nodes, lying on plane, between
+/// nodes on different sides of it.
+#[derive(Debug, Clone, Copy)]
+struct Crossing {
+    first: usize,
+    last: usize,
+    to_front: bool,
+}
+
+impl Crossing {
+    fn back_node(&self) -> usize {
+        if self.to_front {
+            self.first
+        } else {
+            self.last
+        }
+    }
+}
+
+impl<S: Scalar> MeshRefMut<'_, S> {
+    /// Cuts mesh by plane into two new meshes and returns them: first is behind the plane, second
+    /// is in front of it. Polygons, crossing the plane, are split; polygons, lying in the plane,
+    /// go to the half, which they close. When `cap` is set, each half is closed with polygons of
+    /// section, so closed mesh gives two closed halves. This mesh is removed.
+    pub fn split_by_plane(self, plane: Plane<S>, cap: bool) -> anyhow::Result<(MeshId, MeshId)> {
+        let index = self.geo_index;
+        let mesh_id = self.mesh_id;
+
+        let mut moved = Vec::new();
+        let mut crossing = Vec::new();
+        for poly in mesh_id.make_ref(index).all_polygons() {
+            let poly_ref = poly.make_ref(index);
+            let sides = poly_ref
+                .segments()
+                .map(|s| index.plane_side(&plane, s.from()))
+                .collect_vec();
+            match (sides.contains(&Side::Back), sides.contains(&Side::Front)) {
+                (true, true) => crossing.push((poly, index.split_polygon(poly, &plane)?)),
+                (true, false) => moved.push((poly, Side::Back)),
+                (false, true) => moved.push((poly, Side::Front)),
+                (false, false) if poly_ref.normal().dot(&plane.normal()).is_positive() => {
+                    moved.push((poly, Side::Back))
+                }
+                (false, false) => moved.push((poly, Side::Front)),
+            }
+        }
+
+        let back = index.new_mesh();
+        let front = index.new_mesh();
+        for (poly, side) in moved {
+            index.move_polygon(poly, if side == Side::Back { back } else { front });
+        }
+        for (poly, _) in &crossing {
+            index.remove_polygon(poly.poly_id, mesh_id);
+        }
+        for (_, [back_pieces, front_pieces]) in crossing {
+            for piece in back_pieces {
+                index.add_polygon_to_mesh_no_intersect(&piece, back)?;
+            }
+            for piece in front_pieces {
+                index.add_polygon_to_mesh_no_intersect(&piece, front)?;
+            }
+        }
+        index.meshes.remove(&mesh_id);
+
+        if cap {
+            index.cap_section(back, &plane, plane.normal())?;
+            index.cap_section(front, &plane, -plane.normal())?;
+        }
+        Ok((back, front))
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    fn plane_side(&self, plane: &Plane<S>, pt: Vector3<S>) -> Side {
+        let tolerance = self.tolerance();
+        let distance = plane.normal().dot(&pt) - plane.d();
+        if distance > tolerance {
+            Side::Front
+        } else if distance < -tolerance {
+            Side::Back
+        } else {
+            Side::On
+        }
+    }
+
+    /// Splits contour of polygon into pieces behind the plane and in front of it.
+    /// Contour goes to the cut line, then along it to the place, where contour returns to the same
+    /// side, and so on.
+    fn split_polygon(
+        &self,
+        poly: UnrefPoly,
+        plane: &Plane<S>,
+    ) -> anyhow::Result<[Vec<Vec<Vector3<S>>>; 2]> {
+        let poly_ref = poly.make_ref(self);
+        let cut_dir = plane.normal().cross_product(&poly_ref.normal());
+
+        let mut nodes = Vec::new();
+        for seg in poly_ref.oriented_segments() {
+            let (from, to) = (
+                self.plane_side(plane, seg.from()),
+                self.plane_side(plane, seg.to()),
+            );
+            nodes.push((seg.from(), from));
+            if from != Side::On && to != Side::On && from != to {
+                nodes.push((self.rib_cut_point(seg.rib_id(), plane), Side::On));
+            }
+        }
+        let start = nodes
+            .iter()
+            .position(|(_, side)| *side != Side::On)
+            .expect("Polygon crosses plane");
+        nodes.rotate_left(start);
+
+        let off_plane = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, side))| *side != Side::On)
+            .map(|(ix, (_, side))| (ix, *side))
+            .collect_vec();
+        let crossings = off_plane
+            .iter()
+            .zip(off_plane.iter().cycle().skip(1))
+            .filter(|((_, from), (_, to))| from != to)
+            .map(|((from, side), (to, _))| Crossing {
+                first: from + 1,
+                last: if *to == 0 { nodes.len() - 1 } else { to - 1 },
+                to_front: *side == Side::Back,
+            })
+            .collect_vec();
+
+        // Crossings are paired along cut line: polygon goes to front between them.
+        let param = |ix: usize| cut_dir.dot(&nodes[ix].0);
+        let order = (0..crossings.len())
+            .sorted_by(|&a, &b| {
+                param(crossings[a].back_node())
+                    .partial_cmp(&param(crossings[b].back_node()))
+                    .expect("Points are comparable")
+                    .then(crossings[b].to_front.cmp(&crossings[a].to_front))
+            })
+            .collect_vec();
+        let mut partner = HashMap::new();
+        for (&a, &b) in order.iter().tuples() {
+            if crossings[a].to_front || !crossings[b].to_front {
+                bail!("Cannot split polygon {:?} by plane", poly.poly_id);
+            }
+            partner.insert(a, b);
+            partner.insert(b, a);
+        }
+        if partner.len() != crossings.len() {
+            bail!("Cannot split polygon {:?} by plane", poly.poly_id);
+        }
+
+        let on_plane = nodes
+            .iter()
+            .filter(|(_, side)| *side == Side::On)
+            .map(|(pt, _)| (cut_dir.dot(pt), *pt))
+            .collect_vec();
+        let pieces = |to_front: bool| {
+            let mut visited = HashSet::new();
+            let mut pieces = Vec::new();
+            for first in (0..crossings.len()).filter(|&c| crossings[c].to_front == to_front) {
+                if !visited.insert(first) {
+                    continue;
+                }
+                let mut piece: Vec<Vector3<S>> = Vec::new();
+                let mut current = first;
+                loop {
+                    // Contour runs on this side till the next crossing...
+                    let next = (current + 1) % crossings.len();
+                    let (from, to) = (crossings[current].last, crossings[next].first);
+                    let mut ix = from;
+                    loop {
+                        piece.push(nodes[ix].0);
+                        if ix == to {
+                            break;
+                        }
+                        ix = (ix + 1) % nodes.len();
+                    }
+                    // ...and cut line leads back to the crossing, paired with it.
+                    current = partner[&next];
+                    let (from, to) = (param(to), param(crossings[current].last));
+                    piece.extend(
+                        on_plane
+                            .iter()
+                            .filter(|(p, _)| (*p - from) * (to - *p) > S::zero())
+                            .sorted_by(|(a, _), (b, _)| {
+                                ((*a - from).abs())
+                                    .partial_cmp(&(*b - from).abs())
+                                    .expect("Points are comparable")
+                            })
+                            .map(|(_, pt)| *pt),
+                    );
+                    if !visited.insert(current) {
+                        break;
+                    }
+                }
+                piece.dedup();
+                if piece.first() == piece.last() {
+                    piece.pop();
+                }
+                pieces.push(piece);
+            }
+            pieces
+        };
+
+        Ok([pieces(false), pieces(true)])
+    }
+
+    /// Closes section of mesh half with polygons, which normal is `normal`. Ribs in plane, used by
+    /// only one polygon of mesh, bound section. Holes of section are joined to its contour with
+    /// bridges.
+    fn cap_section(
+        &mut self,
+        mesh_id: MeshId,
+        plane: &Plane<S>,
+        normal: Vector3<S>,
+    ) -> anyhow::Result<()> {
+        let mut open: BTreeMap<RibId, Vec<(PtId, PtId)>> = BTreeMap::new();
+        for poly in mesh_id.make_ref(self).all_polygons() {
+            for s in poly.make_ref(self).non_bridge_segments() {
+                open.entry(s.rib_id())
+                    .or_default()
+                    .push((s.from_pt(), s.to_pt()));
+            }
+        }
+
+        let on_plane = |pt: PtId| self.plane_side(plane, self.vertices.get_point(pt)) == Side::On;
+        let mut cuts: BTreeMap<PtId, Vec<PtId>> = BTreeMap::new();
+        for (from, to) in open
+            .into_values()
+            .filter(|usage| usage.len() == 1)
+            .map(|usage| usage[0])
+            .filter(|(from, to)| on_plane(*from) && on_plane(*to))
+        {
+            // Cap goes along the rib in opposite direction.
+            cuts.entry(to).or_default().push(from);
+        }
+
+        let basis = plane_basis(plane);
+        let flip = normal.dot(&plane.normal()).is_negative();
+        let pts: HashMap<PtId, Pt2> = cuts
+            .keys()
+            .map(|pt| {
+                let v = basis.project_on_plane_z(&self.vertices.get_point(*pt));
+                let (x, y) = (
+                    v.x.to_f64().unwrap_or_default(),
+                    v.y.to_f64().unwrap_or_default(),
+                );
+                (*pt, if flip { (x, -y) } else { (x, y) })
+            })
+            .collect();
+
+        let mut outers = Vec::new();
+        let mut holes = Vec::new();
+        for (contour, closed) in chain_cuts(cuts) {
+            if !closed {
+                bail!("Section of mesh {mesh_id:?} is not closed");
+            }
+            let area = contour
+                .iter()
+                .zip(contour.iter().cycle().skip(1))
+                .map(|(a, b)| pts[a].0 * pts[b].1 - pts[a].1 * pts[b].0)
+                .sum::<f64>();
+            if area > 0.0 {
+                outers.push((contour, area, Vec::new()));
+            } else {
+                holes.push(contour);
+            }
+        }
+
+        for hole in holes {
+            let outer = outers
+                .iter_mut()
+                .filter(|(contour, _, _)| is_inside_contour(pts[&hole[0]], contour, &pts))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).expect("Areas are comparable"));
+            match outer {
+                Some((_, _, outer_holes)) => outer_holes.push(hole),
+                None => bail!("Hole in section of mesh {mesh_id:?} is outside of it"),
+            }
+        }
+
+        for (contour, _, holes) in outers {
+            let Some(contour) = bridge_holes(contour, holes, &pts) else {
+                bail!("Cannot join holes of section of mesh {mesh_id:?} to its contour");
+            };
+            let mut vertices = contour
+                .iter()
+                .map(|pt| self.vertices.get_point(*pt))
+                .collect_vec();
+            let corner = convex_corner(&vertices, normal, self.tolerance()).unwrap_or_default();
+            vertices.rotate_left(corner);
+            self.add_polygon_to_mesh_no_intersect(&vertices, mesh_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Joins holes to contour with bridges - ribs, which contour goes through in both directions.
+/// Contour goes counter-clockwise, holes go clockwise. None, when some hole cannot be joined.
+pub(super) fn bridge_holes(
+    mut contour: Vec<PtId>,
+    mut holes: Vec<Vec<PtId>>,
+    pts: &HashMap<PtId, Pt2>,
+) -> Option<Vec<PtId>> {
+    while !holes.is_empty() {
+        let (hole_ix, at, hole_at) = (0..holes.len()).find_map(|hole_ix| {
+            let (at, hole_at) = find_bridge(&contour, &holes, hole_ix, pts)?;
+            Some((hole_ix, at, hole_at))
+        })?;
+        let mut hole = holes.swap_remove(hole_ix);
+        hole.rotate_left(hole_at);
+        let pt = contour[at];
+        hole.push(hole[0]);
+        hole.push(pt);
+        contour.splice(at + 1..at + 1, hole);
+    }
+    Some(contour)
+}
+
+/// Position of first convex corner of contour, which goes counter-clockwise around normal.
+/// Polygon, which contour starts at convex corner, gets its plane right from the first points.
+/// Corners, where ribs turn less than tolerance, are taken as straight.
+pub(super) fn convex_corner<S: Scalar>(
+    contour: &[Vector3<S>],
+    normal: Vector3<S>,
+    tolerance: S,
+) -> Option<usize> {
+    let len = contour.len();
+    (0..len).find(|&ix| {
+        let (prev, cur, next) = (
+            contour[(ix + len - 1) % len],
+            contour[ix],
+            contour[(ix + 1) % len],
+        );
+        (cur - prev).cross_product(&(next - cur)).dot(&normal) > tolerance * tolerance
+    })
+}
+
+/// Finds vertex of contour and vertex of hole, which can be joined with a bridge: it goes
+/// inside of polygon and crosses nothing. Nearest vertices are tried first.
+fn find_bridge(
+    contour: &[PtId],
+    holes: &[Vec<PtId>],
+    hole_ix: usize,
+    pts: &HashMap<PtId, Pt2>,
+) -> Option<(usize, usize)> {
+    let hole = &holes[hole_ix];
+    let loops = std::iter::once(contour).chain(holes.iter().map(|h| h.as_slice()));
+    let edges = loops
+        .flat_map(|l| l.iter().zip(l.iter().cycle().skip(1)))
+        .map(|(a, b)| (*a, *b))
+        .collect_vec();
+
+    (0..contour.len())
+        .cartesian_product(0..hole.len())
+        .sorted_by(|&(a, b), &(c, d)| {
+            distance_sq(pts[&contour[a]], pts[&hole[b]])
+                .partial_cmp(&distance_sq(pts[&contour[c]], pts[&hole[d]]))
+                .expect("Distances are comparable")
+        })
+        .find(|&(at, hole_at)| {
+            let (from, to) = (contour[at], hole[hole_at]);
+            let (a, b) = (pts[&from], pts[&to]);
+            inside_corner(contour, at, b, pts)
+                && inside_corner(hole, hole_at, a, pts)
+                && !edges.iter().any(|&(c, d)| {
+                    if [c, d].contains(&from) || [c, d].contains(&to) {
+                        return false;
+                    }
+                    let (c, d) = (pts[&c], pts[&d]);
+                    let touches = |p: Pt2| cross(a, b, p).abs() < 1e-9 && between(a, b, p);
+                    (cross(a, b, c) * cross(a, b, d) < 0.0 && cross(c, d, a) * cross(c, d, b) < 0.0)
+                        || touches(c)
+                        || touches(d)
+                })
+        })
+}
+
+/// Checks, that direction from contour vertex to point goes inside of polygon: polygon is on the
+/// left of contour, so inside is between next and previous vertices counter-clockwise.
+fn inside_corner(contour: &[PtId], at: usize, to: Pt2, pts: &HashMap<PtId, Pt2>) -> bool {
+    let len = contour.len();
+    let origin = pts[&contour[at]];
+    let next = pts[&contour[(at + 1) % len]];
+    let prev = pts[&contour[(at + len - 1) % len]];
+    let angle = |p: Pt2| {
+        let a =
+            (p.1 - origin.1).atan2(p.0 - origin.0) - (next.1 - origin.1).atan2(next.0 - origin.0);
+        a.rem_euclid(std::f64::consts::TAU)
+    };
+    let limit = match angle(prev) {
+        a if a < 1e-12 => std::f64::consts::TAU,
+        a => a,
+    };
+    let test = angle(to);
+    test > 1e-12 && test < limit
+}
+
+/// Even-odd test: point is inside, when ray from it crosses contour odd number of times.
+fn is_inside_contour(pt: Pt2, contour: &[PtId], pts: &HashMap<PtId, Pt2>) -> bool {
+    contour
+        .iter()
+        .zip(contour.iter().cycle().skip(1))
+        .filter(|(from, to)| {
+            let (a, b) = (pts[from], pts[to]);
+            if (a.1 > pt.1) == (b.1 > pt.1) {
+                return false;
+            }
+            let x = a.0 + (pt.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+            x > pt.0
+        })
+        .count()
+        % 2
+        == 1
+}
+
+fn cross(o: Pt2, a: Pt2, b: Pt2) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn between(a: Pt2, b: Pt2, p: Pt2) -> bool {
+    let t = ((p.0 - a.0) * (b.0 - a.0) + (p.1 - a.1) * (b.1 - a.1)) / distance_sq(a, b);
+    t > 0.0 && t < 1.0
+}
+
+fn distance_sq(a: Pt2, b: Pt2) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::{
+        indexes::geo_index::{
+            geo_object::GeoObject,
+            mesh::tests::{cube, new_index},
+        },
+        planar::plane::Plane,
+    };
+
+    #[test]
+    fn split_by_plane_keeps_volume() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+        let mesh_id = index.difference(a, b).unwrap();
+        let plane = Plane::new_from_normal_and_point(
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.3, 0.0, 0.0),
+        );
+
+        let (back, front) = mesh_id
+            .make_mut_ref(&mut index)
+            .split_by_plane(plane, true)
+            .unwrap();
+        let (back, front) = (back.make_ref(&index), front.make_ref(&index));
+        assert!(back.validate().is_valid());
+        assert!(front.validate().is_valid());
+        assert!((back.volume() - 5.2).abs() < 1e-9);
+        assert!((front.volume() - 2.675).abs() < 1e-9);
+        assert!((back.volume() + front.volume() - 7.875).abs() < 1e-9);
+        assert!(back.bounding_box().unwrap().max.x < 0.3 + 1e-9);
+    }
+
+    #[test]
+    fn split_hollow_cube_through_cavity() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 4.0);
+        let b = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mesh_id = index.difference(a, b).unwrap();
+        let plane = Plane::new_from_normal_and_point(
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.2, 0.0, 0.0),
+        );
+
+        // Section is a square with square hole, so caps are joined to holes with bridges.
+        let (back, front) = mesh_id
+            .make_mut_ref(&mut index)
+            .split_by_plane(plane, true)
+            .unwrap();
+        let (back, front) = (back.make_ref(&index), front.make_ref(&index));
+        assert!(back.validate().is_valid());
+        assert!(front.validate().is_valid());
+        assert!((back.volume() - 34.5).abs() < 1e-9);
+        assert!((front.volume() - 28.5).abs() < 1e-9);
+    }
+}
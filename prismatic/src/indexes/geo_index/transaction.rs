@@ -0,0 +1,343 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    ops::Deref,
+};
+
+use anyhow::anyhow;
+use math::Scalar;
+
+use super::{index::GeoIndex, poly_rtree::FaceRtreeRecord};
+
+/// Changes of maps, which index is made of.
+pub(crate) trait MapMut {
+    type Key: Ord + Clone;
+    type Value: Clone;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value>;
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value>;
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value>;
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value>;
+}
+
+impl<K: Ord + Clone, V: Clone> MapMut for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn get(&self, key: &K) -> Option<&V> {
+        BTreeMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        BTreeMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        BTreeMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        BTreeMap::remove(self, key)
+    }
+}
+
+impl<K: Ord + Hash + Clone, V: Clone> MapMut for HashMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn get(&self, key: &K) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        HashMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        HashMap::remove(self, key)
+    }
+}
+
+/// Map, which keeps value of each entry, as it was before the first change in transaction, so
+/// that changes can be undone. Map is read through `Deref`, and changed only through methods,
+/// which keep the old value.
+#[derive(Debug)]
+pub(crate) struct Journaled<M: MapMut> {
+    map: M,
+    journal: Option<BTreeMap<M::Key, Option<M::Value>>>,
+}
+
+impl<M: MapMut> Journaled<M> {
+    fn record(&mut self, key: &M::Key) {
+        if let Some(journal) = &mut self.journal {
+            if !journal.contains_key(key) {
+                journal.insert(key.clone(), self.map.get(key).cloned());
+            }
+        }
+    }
+
+    fn begin(&mut self) {
+        self.journal = Some(BTreeMap::new());
+    }
+
+    fn commit(&mut self) {
+        self.journal = None;
+    }
+
+    /// Keys of entries, changed since transaction began.
+    fn changed_keys(&self) -> Vec<M::Key> {
+        self.journal
+            .iter()
+            .flat_map(|journal| journal.keys().cloned())
+            .collect()
+    }
+
+    fn rollback(&mut self) {
+        for (key, value) in self.journal.take().into_iter().flatten() {
+            match value {
+                Some(value) => self.map.insert(key, value),
+                None => self.map.remove(&key),
+            };
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> M {
+        self.map
+    }
+}
+
+impl<M: MapMut> MapMut for Journaled<M> {
+    type Key = M::Key;
+    type Value = M::Value;
+
+    fn get(&self, key: &M::Key) -> Option<&M::Value> {
+        self.map.get(key)
+    }
+
+    fn get_mut(&mut self, key: &M::Key) -> Option<&mut M::Value> {
+        self.record(key);
+        self.map.get_mut(key)
+    }
+
+    fn insert(&mut self, key: M::Key, value: M::Value) -> Option<M::Value> {
+        self.record(&key);
+        self.map.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &M::Key) -> Option<M::Value> {
+        self.record(key);
+        self.map.remove(key)
+    }
+}
+
+impl<M: MapMut> Deref for Journaled<M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.map
+    }
+}
+
+impl<M: MapMut + Default> Default for Journaled<M> {
+    fn default() -> Self {
+        M::default().into()
+    }
+}
+
+impl<M: MapMut + FromIterator<(M::Key, M::Value)>> FromIterator<(M::Key, M::Value)>
+    for Journaled<M>
+{
+    fn from_iter<T: IntoIterator<Item = (M::Key, M::Value)>>(iter: T) -> Self {
+        M::from_iter(iter).into()
+    }
+}
+
+impl<M: MapMut> From<M> for Journaled<M> {
+    fn from(map: M) -> Self {
+        Self { map, journal: None }
+    }
+}
+
+/// Counters of index, when transaction began.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TransactionStart {
+    rib_counter: usize,
+    face_counter: usize,
+    mesh_counter: usize,
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Starts recording changes of index, so that they can be undone with `rollback`. Only
+    /// entries, which are changed, are copied - index is not cloned. Transactions do not nest.
+    pub fn begin_transaction(&mut self) -> anyhow::Result<()> {
+        if self.transaction.is_some() {
+            return Err(anyhow!("Transaction is already started"));
+        }
+        self.transaction = Some(TransactionStart {
+            rib_counter: self.rib_counter,
+            face_counter: self.face_counter,
+            mesh_counter: self.mesh_counter,
+        });
+        self.faces.begin();
+        self.ribs.begin();
+        self.meshes.begin();
+        self.rib_to_face.begin();
+        self.pt_to_ribs.begin();
+        self.partially_split_faces.begin();
+        self.face_splits.begin();
+        self.rib_parent.begin();
+        self.deleted_faces.begin();
+        self.split_ribs.begin();
+        Ok(())
+    }
+
+    /// Keeps all changes, made since `begin_transaction`.
+    pub fn commit(&mut self) -> anyhow::Result<()> {
+        if self.transaction.is_none() {
+            return Err(anyhow!("No transaction to commit"));
+        }
+        self.end_transaction();
+        Ok(())
+    }
+
+    /// Brings faces, ribs, meshes, their indexes and counters back to the state, they had at
+    /// `begin_transaction`. Points, added in transaction, stay in index, but nothing uses them.
+    pub fn rollback(&mut self) -> anyhow::Result<()> {
+        let Some(start) = self.transaction.take() else {
+            return Err(anyhow!("No transaction to roll back"));
+        };
+
+        let changed_faces = self.faces.changed_keys();
+        for face_id in &changed_faces {
+            if let Some(face) = self.faces.get(face_id) {
+                self.face_index
+                    .remove(&FaceRtreeRecord(*face_id, *face.aabb()));
+            }
+        }
+        self.faces.rollback();
+        for face_id in changed_faces {
+            if let Some(face) = self.faces.get(&face_id) {
+                self.face_index
+                    .insert(FaceRtreeRecord(face_id, *face.aabb()));
+            }
+        }
+
+        self.ribs.rollback();
+        self.meshes.rollback();
+        self.rib_to_face.rollback();
+        self.pt_to_ribs.rollback();
+        self.partially_split_faces.rollback();
+        self.face_splits.rollback();
+        self.rib_parent.rollback();
+        self.deleted_faces.rollback();
+        self.split_ribs.rollback();
+        self.rib_counter = start.rib_counter;
+        self.face_counter = start.face_counter;
+        self.mesh_counter = start.mesh_counter;
+        Ok(())
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    fn end_transaction(&mut self) {
+        self.transaction = None;
+        self.faces.commit();
+        self.ribs.commit();
+        self.meshes.commit();
+        self.rib_to_face.commit();
+        self.pt_to_ribs.commit();
+        self.partially_split_faces.commit();
+        self.face_splits.commit();
+        self.rib_parent.commit();
+        self.deleted_faces.commit();
+        self.split_ribs.commit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        face::FaceId,
+        geo_object::GeoObject,
+        index::GeoIndex,
+        mesh::{
+            tests::{cube, new_index},
+            Mesh, MeshId,
+        },
+        rib::{Rib, RibId},
+    };
+
+    type State = (
+        String,
+        BTreeMap<RibId, Rib>,
+        HashMap<MeshId, Mesh>,
+        BTreeMap<RibId, Vec<FaceId>>,
+        [usize; 3],
+    );
+
+    /// Maps and counters, which transaction must bring back. Faces are compared by their
+    /// debug output, as they have no `PartialEq`.
+    fn state(index: &GeoIndex<f64>) -> State {
+        (
+            format!("{:?}", *index.faces),
+            index.ribs.clone(),
+            index.meshes.clone(),
+            index.rib_to_face.clone(),
+            [index.rib_counter, index.face_counter, index.mesh_counter],
+        )
+    }
+
+    #[test]
+    fn rollback_restores_index() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+        let before = state(&index);
+
+        index.begin_transaction().unwrap();
+        assert!(index.begin_transaction().is_err());
+        let result = index.difference(a, b).unwrap();
+        assert_ne!(state(&index), before);
+        assert!(index.compact().is_err());
+        index.rollback().unwrap();
+
+        assert!(!index.in_transaction());
+        assert_eq!(state(&index), before);
+        assert!(index.meshes.get(&result).is_none());
+        assert!((a.make_ref(&index).volume() - 8.0).abs() < 1e-9);
+        assert!((b.make_ref(&index).volume() - 1.0).abs() < 1e-9);
+        assert!(a.make_ref(&index).validate().is_valid());
+
+        let result = index.difference(a, b).unwrap();
+        assert!((result.make_ref(&index).volume() - 7.875).abs() < 1e-9);
+    }
+
+    #[test]
+    fn commit_keeps_changes() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(1.0, 1.0, 1.0), 1.0);
+
+        index.begin_transaction().unwrap();
+        let result = index.difference(a, b).unwrap();
+        let after = state(&index);
+        index.commit().unwrap();
+
+        assert!(!index.in_transaction());
+        assert_eq!(state(&index), after);
+        assert!((result.make_ref(&index).volume() - 7.875).abs() < 1e-9);
+        assert!(index.rollback().is_err());
+        assert!(index.commit().is_err());
+        assert!(index.compact().is_ok());
+    }
+}
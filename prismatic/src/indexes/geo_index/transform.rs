@@ -0,0 +1,156 @@
+use itertools::Itertools;
+use math::{BaseOrigin, Scalar, Vector3};
+
+use super::{
+    geo_object::GeoObject,
+    index::GeoIndex,
+    mesh::{MeshId, MeshRefMut},
+    split::convex_corner,
+};
+
+impl<S: Scalar> MeshRefMut<'_, S> {
+    /// Moves and rotates mesh by origin: each polygon is removed and added again with vertices,
+    /// which origin puts to new place, so intersections with other meshes are found anew.
+    pub fn transform(&mut self, origin: &BaseOrigin<S>) -> anyhow::Result<()> {
+        let mesh_id = self.mesh_id;
+        let polygons = self.geo_index.transformed_polygons(mesh_id, origin);
+        for poly in self.all_polygons() {
+            self.geo_index.remove_polygon(poly.poly_id, mesh_id);
+        }
+        for vertices in polygons {
+            self.geo_index.add_polygon_to_mesh(&vertices, mesh_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Adds new mesh, which is a copy of given one, put to new place by origin. Polygons are
+    /// added with intersections, as if they came from shape.
+    pub fn duplicate_mesh(
+        &mut self,
+        mesh_id: MeshId,
+        origin: &BaseOrigin<S>,
+    ) -> anyhow::Result<MeshId> {
+        let polygons = self.transformed_polygons(mesh_id, origin);
+        let copy = self.new_mesh();
+        for vertices in polygons {
+            self.add_polygon_to_mesh(&vertices, copy)?;
+        }
+        Ok(copy)
+    }
+
+    /// Contours of mesh polygons with vertices, rotated and moved by origin. Rotation keeps
+    /// direction of contours, so polygons look the same way after it. Each contour starts at
+    /// convex corner, see `convex_corner`.
+    fn transformed_polygons(
+        &self,
+        mesh_id: MeshId,
+        origin: &BaseOrigin<S>,
+    ) -> Vec<Vec<Vector3<S>>> {
+        mesh_id
+            .make_ref(self)
+            .all_polygons()
+            .into_iter()
+            .map(|poly| {
+                let poly_ref = poly.make_ref(self);
+                let normal = poly_ref.normal();
+                let mut contour = poly_ref.oriented_segments().map(|s| s.from()).collect_vec();
+                let corner = convex_corner(&contour, normal, self.tolerance()).unwrap_or_default();
+                contour.rotate_left(corner);
+                contour
+                    .into_iter()
+                    .map(|pt| origin.rotation * pt + origin.center)
+                    .collect_vec()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use math::{BaseOrigin, CrossProduct, Vector3};
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+        seg::SegmentDir,
+    };
+
+    #[test]
+    fn transform_cube() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let origin = BaseOrigin::new().offset_x(5.0).rotate_z(FRAC_PI_2 / 2.0);
+        a.make_mut_ref(&mut index).transform(&origin).unwrap();
+
+        let mesh = a.make_ref(&index);
+        assert!((mesh.volume() - 8.0).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+        let aabb = mesh.bounding_box().unwrap();
+        // Cube, turned around z by half of right angle, spans half of its diagonal along x and y.
+        let d = 2.0_f64.sqrt();
+        assert!((aabb.min - Vector3::new(5.0 - d, -d, -1.0)).magnitude() < 1e-9);
+        assert!((aabb.max - Vector3::new(5.0 + d, d, 1.0)).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn duplicate_crosses_other_meshes() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let copy = index
+            .duplicate_mesh(a, &BaseOrigin::new().offset(Vector3::new(1.0, 1.0, 1.0)))
+            .unwrap();
+
+        assert!((copy.make_ref(&index).volume() - 8.0).abs() < 1e-9);
+        assert!((a.make_ref(&index).volume() - 8.0).abs() < 1e-9);
+        let union = index.union(a, copy).unwrap();
+        assert!((union.make_ref(&index).volume() - 15.0).abs() < 1e-9);
+        assert!(union.make_ref(&index).validate().is_valid());
+    }
+
+    #[test]
+    fn duplicate_face_with_hole() {
+        let mut index = new_index();
+        let mesh_id = index.new_mesh();
+        let v = |x: f64, y: f64| Vector3::new(x, y, 0.0);
+        let keyhole = [
+            v(-2.0, -2.0),
+            v(2.0, -2.0),
+            v(2.0, 2.0),
+            v(-2.0, 2.0),
+            v(-2.0, -2.0),
+            v(-1.0, -1.0),
+            v(-1.0, 1.0),
+            v(1.0, 1.0),
+            v(1.0, -1.0),
+            v(-1.0, -1.0),
+        ];
+        mesh_id
+            .make_mut_ref(&mut index)
+            .add_polygon(&keyhole)
+            .unwrap();
+        let origin = BaseOrigin::new().offset_z(3.0).rotate_x(FRAC_PI_2);
+        let copy = index.duplicate_mesh(mesh_id, &origin).unwrap();
+
+        let polygons = copy.make_ref(&index).all_polygons();
+        assert_eq!(polygons.len(), 1);
+        let poly = polygons[0].make_ref(&index);
+        assert!((poly.normal() - Vector3::new(0.0, -1.0, 0.0)).magnitude() < 1e-9);
+        let face = poly.face_id().make_ref(&index);
+        assert_eq!(face.segments(SegmentDir::Fow).count(), 10);
+        assert_eq!(face.non_bridge_segments(SegmentDir::Fow).len(), 8);
+        let triangles = face.triangulate().unwrap();
+        let area: f64 = triangles
+            .iter()
+            .map(|[a, b, c]| (b - a).cross_product(&(c - a)).magnitude() / 2.0)
+            .sum();
+        assert!((area - 12.0).abs() < 1e-9);
+        for pt in triangles.iter().flatten() {
+            assert!(pt.y.abs() < 1e-9);
+            assert!((pt.z - 3.0).abs() <= 2.0 + 1e-9);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use itertools::Itertools;
+use math::Scalar;
+
+use crate::indexes::vertex_index::PtId;
+
+use super::{
+    face::FaceId, geo_object::GeoObject, mesh::MeshRef, poly::UnrefPoly, rib::RibId,
+    seg::SegmentDir,
+};
+
+/// Problems, found in mesh. Empty report means, that mesh is closed two-manifold with
+/// consistently oriented polygons.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeshValidation {
+    /// Ribs, used by only one polygon of mesh.
+    pub open_ribs: Vec<RibId>,
+    /// Ribs, used by more than two polygons of mesh.
+    pub non_manifold_ribs: Vec<RibId>,
+    /// Ribs, where two adjacent polygons go in same direction, so one of them is flipped.
+    pub inconsistent_ribs: Vec<RibId>,
+    /// Points, which lie on no polygon of mesh, though parts of its split ribs end there, and
+    /// vertices, which lie inside of rib of neighbouring polygon (T-junctions).
+    pub isolated_vertices: Vec<PtId>,
+}
+
+impl MeshValidation {
+    pub fn is_valid(&self) -> bool {
+        self.open_ribs.is_empty()
+            && self.non_manifold_ribs.is_empty()
+            && self.inconsistent_ribs.is_empty()
+            && self.isolated_vertices.is_empty()
+    }
+}
+
+impl<S: Scalar> MeshRef<'_, S> {
+    /// Checks, that mesh is closed, manifold and consistently oriented.
+    pub fn validate(&self) -> MeshValidation {
+        let mut face_polies: HashMap<FaceId, Vec<UnrefPoly>> = HashMap::new();
+        for poly in self.all_polygons() {
+            let face_id = poly.make_ref(self.geo_index).face_id();
+            face_polies.entry(face_id).or_default().push(poly);
+        }
+
+        // Direction of rib in each polygon, which uses it. Bridges, connecting holes of the face
+        // with its outer contour, are used twice by same face - they are not edges of mesh.
+        let mut rib_usage: BTreeMap<RibId, Vec<(UnrefPoly, SegmentDir)>> = BTreeMap::new();
+        let mut boundary: BTreeSet<PtId> = BTreeSet::new();
+        for (face_id, polies) in &face_polies {
+            let segs = face_id
+                .make_ref(self.geo_index)
+                .non_bridge_segments(SegmentDir::Fow);
+            for poly in polies {
+                let poly_dir = poly.make_ref(self.geo_index).dir();
+                for seg in &segs {
+                    boundary.insert(seg.from_pt());
+                    let dir = match poly_dir {
+                        SegmentDir::Fow => seg.dir,
+                        SegmentDir::Rev => seg.dir.flip(),
+                    };
+                    rib_usage
+                        .entry(seg.rib_id())
+                        .or_default()
+                        .push((*poly, dir));
+                }
+            }
+        }
+
+        let mut report = MeshValidation::default();
+        for (rib_id, usage) in &rib_usage {
+            match usage.as_slice() {
+                [_] => report.open_ribs.push(*rib_id),
+                [(_, a), (_, b)] if a == b => report.inconsistent_ribs.push(*rib_id),
+                [_, _] => {}
+                _ => report.non_manifold_ribs.push(*rib_id),
+            }
+        }
+
+        let index = self.geo_index;
+        // Parts of split ribs meet at points, which polygons, still using whole rib, miss.
+        let split_ends = rib_usage
+            .keys()
+            .filter(|rib_id| index.split_ribs.contains_key(rib_id))
+            .flat_map(|rib_id| index.get_ribs_with_root_parent(*rib_id))
+            .filter_map(|rib_id| index.ribs.get(&rib_id))
+            .flat_map(|rib| [rib.0, rib.1])
+            .filter(|pt| !boundary.contains(pt));
+        let t_junctions = face_polies.keys().flat_map(|face_id| {
+            face_id
+                .make_ref(index)
+                .non_bridge_segments(SegmentDir::Fow)
+                .iter()
+                .flat_map(|s| index.inner_points(s, &boundary))
+                .collect_vec()
+        });
+        report.isolated_vertices = split_ends.chain(t_junctions).sorted().dedup().collect();
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        index::GeoIndex,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn closed_cube_is_valid() {
+        let mut index = new_index();
+        let mesh_id = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let report = mesh_id.make_ref(&index).validate();
+        assert!(report.is_valid(), "{report:?}");
+    }
+
+    #[test]
+    fn open_surface() {
+        let mut index = new_index();
+        let mesh_id = index.new_mesh();
+        let quad = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ];
+        index.add_polygon_to_mesh(&quad, mesh_id).unwrap();
+
+        let report = mesh_id.make_ref(&index).validate();
+        assert_eq!(report.open_ribs.len(), 4);
+        assert!(report.isolated_vertices.is_empty());
+        assert!(report.non_manifold_ribs.is_empty());
+        assert!(report.inconsistent_ribs.is_empty());
+    }
+
+    #[test]
+    fn non_manifold_edge() {
+        let mut index = new_index();
+        let mesh_id = index.new_mesh();
+        // Three fins around common edge on z axis.
+        for dir in [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(-1.0, -1.0, 0.0),
+        ] {
+            let quad = [
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 0.0, 1.0),
+                dir + Vector3::new(0.0, 0.0, 1.0),
+                dir,
+            ];
+            index.add_polygon_to_mesh(&quad, mesh_id).unwrap();
+        }
+
+        let report = mesh_id.make_ref(&index).validate();
+        assert_eq!(report.non_manifold_ribs.len(), 1);
+        assert_eq!(report.open_ribs.len(), 9);
+        assert!(report.isolated_vertices.is_empty());
+    }
+
+    #[test]
+    fn isolated_vertex_of_split_rib() {
+        let mut index = new_index();
+        let mesh_id = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        // Rib is split, but no face around it uses the parts, so the middle of the rib lies on
+        // no polygon of mesh.
+        let rib_id = *index.rib_to_face.keys().next().unwrap();
+        let rib = index.ribs[&rib_id];
+        let (from, to) = (
+            index.vertices.get_point(rib.0),
+            index.vertices.get_point(rib.1),
+        );
+        let middle = (from + to) / 2.0;
+        for part in [(from, middle), (middle, to)] {
+            let seg = index.save_segment(part).unwrap();
+            GeoIndex::<f64>::save_index(&mut index.split_ribs, rib_id, seg.rib_id);
+        }
+
+        let report = mesh_id.make_ref(&index).validate();
+        assert_eq!(report.isolated_vertices.len(), 1);
+        assert_eq!(
+            index.vertices.get_point(report.isolated_vertices[0]),
+            middle
+        );
+        assert!(report.open_ribs.is_empty());
+        assert!(!report.is_valid());
+    }
+}
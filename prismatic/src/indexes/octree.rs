@@ -216,4 +216,8 @@ impl<T: Clone, S: Scalar> Octree<T, S> {
     pub fn set_aabb(&mut self, aabb: Aabb<S>) {
         self.aabb = aabb
     }
+
+    pub fn aabb(&self) -> &Aabb<S> {
+        &self.aabb
+    }
 }
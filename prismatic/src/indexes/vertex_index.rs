@@ -17,6 +17,7 @@ pub struct VertexIndex<S> {
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PtId(usize);
 
 impl PartialEq<usize> for PtId {
@@ -39,6 +40,31 @@ impl<S: Scalar> VertexIndex<S> {
         }
     }
 
+    /// Builds index from points, which are already known to be separated.
+    pub(crate) fn from_points(aabb: Aabb<S>, points: Vec<Vector3<S>>) -> Self {
+        let nodes = points
+            .iter()
+            .enumerate()
+            .map(|(id, point)| Node {
+                data: id,
+                point: *point,
+            })
+            .collect_vec();
+        Self {
+            octree: Octree::new_with_aabb(nodes, aabb),
+            points,
+        }
+    }
+
+    pub(crate) fn aabb(&self) -> Aabb<S> {
+        *self.octree.aabb()
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn points(&self) -> &[Vector3<S>] {
+        &self.points
+    }
+
     pub fn get_or_insert_point(&mut self, vertex: Vector3<S>, separation_distance: S) -> PtId {
         if let Some(n) = self.find_closest(vertex, separation_distance) {
             n
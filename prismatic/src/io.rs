@@ -0,0 +1,83 @@
+use anyhow::anyhow;
+use math::{Scalar, Vector3};
+
+use crate::indexes::geo_index::{face::FaceId, geo_object::GeoObject, index::GeoIndex, mesh::MeshId};
+
+pub mod obj;
+pub mod off;
+pub mod ply;
+pub mod stl;
+pub mod three_mf;
+
+/// Result of loading a file into index.
+#[derive(Debug)]
+pub struct Import<S: Scalar> {
+    pub mesh_id: MeshId,
+    pub rejected: Vec<RejectedPolygon<S>>,
+}
+
+/// Polygon, which could not be added to index, usually because it is degenerate: its vertices
+/// are merged or lay on the same line with respect to points precision of index.
+#[derive(Debug)]
+pub struct RejectedPolygon<S: Scalar> {
+    /// Number of polygon in file.
+    pub ix: usize,
+    pub vertices: Vec<Vector3<S>>,
+    pub reason: String,
+}
+
+/// Adds polygons to new mesh one by one. Polygons, rejected by index, are collected in result.
+pub(crate) fn import_polygons<S: Scalar>(
+    index: &mut GeoIndex<S>,
+    polygons: impl IntoIterator<Item = Vec<Vector3<S>>>,
+) -> Import<S> {
+    let mesh_id = index.new_mesh();
+    let mut rejected = Vec::new();
+
+    for (ix, vertices) in polygons.into_iter().enumerate() {
+        if let Err(err) = index.add_polygon_to_mesh(&vertices, mesh_id) {
+            rejected.push(RejectedPolygon {
+                ix,
+                vertices,
+                reason: err.to_string(),
+            });
+        }
+    }
+
+    Import { mesh_id, rejected }
+}
+
+/// Polygon of some mesh with vertices as indices in shared point list.
+pub(crate) struct IndexedPolygon {
+    pub(crate) mesh_id: MeshId,
+    pub(crate) face_id: FaceId,
+    pub(crate) vertices: Vec<usize>,
+}
+
+/// Collects polygons of several meshes into single point list.
+pub(crate) fn collect_polygons<S: Scalar>(
+    index: &GeoIndex<S>,
+    meshes: &[MeshId],
+) -> anyhow::Result<(Vec<Vector3<S>>, Vec<IndexedPolygon>)> {
+    check_meshes(index, meshes)?;
+    let mut points = Vec::new();
+    let mut polygons = Vec::new();
+    for mesh_id in meshes {
+        let (mesh_points, mesh_polygons) = mesh_id.make_ref(index).compact_polygons();
+        let offset = points.len();
+        points.extend(mesh_points);
+        polygons.extend(mesh_polygons.into_iter().map(|mut polygon| {
+            polygon.vertices.iter_mut().for_each(|ix| *ix += offset);
+            polygon
+        }));
+    }
+    Ok((points, polygons))
+}
+
+pub(crate) fn check_meshes<S: Scalar>(index: &GeoIndex<S>, meshes: &[MeshId]) -> anyhow::Result<()> {
+    let known = index.meshes().into_iter().map(|m| m.mesh_id()).collect::<Vec<_>>();
+    if let Some(mesh_id) = meshes.iter().find(|mesh_id| !known.contains(mesh_id)) {
+        return Err(anyhow!("Mesh id {mesh_id:?} not found"));
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::io::{BufRead, Write};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use math::{Scalar, Vector3};
+
+use crate::indexes::geo_index::{geo_object::GeoObject, index::GeoIndex, mesh::MeshRef};
+
+use super::{import_polygons, Import};
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Writes all meshes of index as Wavefront OBJ, one object per mesh.
+    pub fn write_obj<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let mut offset = 0;
+        for mesh_id in self.meshes().into_iter().map(|m| m.mesh_id()).sorted() {
+            offset += write_object(writer, &mesh_id.make_ref(self), offset)?;
+        }
+        Ok(())
+    }
+
+    /// Loads all faces of Wavefront OBJ into new mesh. Groups and objects are ignored.
+    /// Faces, rejected by index, are skipped and reported in result.
+    pub fn load_obj<R: BufRead>(&mut self, reader: R) -> anyhow::Result<Import<S>> {
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+
+        for (line_ix, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut items = line.split_whitespace();
+            match items.next() {
+                Some("v") => {
+                    let coords = items
+                        .take(3)
+                        .map(|c| c.parse::<f64>().map(S::from_value))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|err| anyhow!("Line {}: bad vertex: {err}", line_ix + 1))?;
+                    if coords.len() != 3 {
+                        return Err(anyhow!("Line {}: vertex needs 3 coordinates", line_ix + 1));
+                    }
+                    vertices.push(Vector3::new(coords[0], coords[1], coords[2]));
+                }
+                Some("f") => {
+                    let face = items
+                        .map(|item| Self::obj_vertex(item, vertices.len()))
+                        .collect::<anyhow::Result<Vec<_>>>()
+                        .map_err(|err| anyhow!("Line {}: {err}", line_ix + 1))?;
+                    faces.push(face);
+                }
+                _ => {}
+            }
+        }
+
+        let polygons = faces
+            .into_iter()
+            .map(|face| face.into_iter().map(|ix| vertices[ix]).collect_vec())
+            .collect_vec();
+
+        Ok(import_polygons(self, polygons))
+    }
+
+    /// Parses vertex reference of face record: `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    /// Negative indices are relative to end of vertex list, read so far.
+    fn obj_vertex(item: &str, vertices_len: usize) -> anyhow::Result<usize> {
+        let ix = item
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .parse::<isize>()
+            .map_err(|err| anyhow!("bad face vertex `{item}`: {err}"))?;
+        let ix = match ix {
+            ix if ix > 0 => ix - 1,
+            ix if ix < 0 => vertices_len as isize + ix,
+            _ => -1,
+        };
+        if ix < 0 || ix as usize >= vertices_len {
+            return Err(anyhow!("face vertex `{item}` is out of range"));
+        }
+        Ok(ix as usize)
+    }
+}
+
+/// Writes mesh as named object. Indices of face vertices are shifted by `offset` - number of
+/// vertices, already written to the file. Returns number of written vertices.
+pub(crate) fn write_object<S: Scalar, W: Write>(
+    writer: &mut W,
+    mesh: &MeshRef<'_, S>,
+    offset: usize,
+) -> anyhow::Result<usize> {
+    let (points, polygons) = mesh.compact_polygons();
+    writeln!(writer, "o mesh_{}", mesh.mesh_id().0)?;
+    for v in &points {
+        writeln!(writer, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    for polygon in polygons {
+        let ixs = polygon.vertices.into_iter().map(|ix| ix + offset + 1).join(" ");
+        writeln!(writer, "f {ixs}")?;
+    }
+    Ok(points.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn obj_round_trip() {
+        let mut index = new_index();
+        cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+        let mut buffer = Vec::new();
+        index.write_obj(&mut buffer).unwrap();
+        let obj = String::from_utf8(buffer).unwrap();
+        assert_eq!(obj.lines().filter(|l| l.starts_with("o ")).count(), 2);
+
+        let mut loaded = new_index();
+        let import = loaded.load_obj(obj.as_bytes()).unwrap();
+        assert!(import.rejected.is_empty());
+        let mesh = import.mesh_id.make_ref(&loaded);
+        // Faces are written as polygons, not triangles.
+        assert_eq!(mesh.all_polygons().len(), 12);
+        assert_eq!(mesh.triangulated().unwrap().len(), 24);
+        assert!((mesh.volume() - 9.0).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+    }
+}
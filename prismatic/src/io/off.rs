@@ -0,0 +1,127 @@
+use std::io::{BufRead, Write};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use math::{Scalar, Vector3};
+
+use crate::indexes::geo_index::{index::GeoIndex, mesh::MeshId};
+
+use super::{collect_polygons, import_polygons, Import};
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Writes meshes as single ASCII OFF file. OFF has no place for face attributes, so meshes
+    /// cannot be told apart after loading.
+    pub fn write_off<W: Write>(&self, writer: &mut W, meshes: &[MeshId]) -> anyhow::Result<()> {
+        let (points, polygons) = collect_polygons(self, meshes)?;
+        writeln!(writer, "OFF")?;
+        writeln!(writer, "{} {} 0", points.len(), polygons.len())?;
+        for v in points {
+            writeln!(writer, "{} {} {}", v.x, v.y, v.z)?;
+        }
+        for polygon in polygons {
+            writeln!(
+                writer,
+                "{} {}",
+                polygon.vertices.len(),
+                polygon.vertices.iter().join(" ")
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Loads faces of ASCII OFF into new mesh. Face colors are ignored.
+    /// Faces, rejected by index, are skipped and reported in result.
+    pub fn load_off<R: BufRead>(&mut self, reader: R) -> anyhow::Result<Import<S>> {
+        let lines = reader
+            .lines()
+            .map(|line| line.map(|l| l.split('#').next().unwrap_or_default().trim().to_owned()))
+            .filter_ok(|l| !l.is_empty())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut lines = lines.iter().map(|l| l.split_whitespace().collect_vec());
+
+        let mut header = lines.next().ok_or_else(|| anyhow!("Empty OFF file"))?;
+        if header.first() != Some(&"OFF") {
+            return Err(anyhow!("Not an OFF file"));
+        }
+        // Counts may follow `OFF` keyword on the same line.
+        header.remove(0);
+        if header.is_empty() {
+            header = lines.next().ok_or_else(|| anyhow!("OFF counts are missing"))?;
+        }
+        let counts = header
+            .iter()
+            .map(|c| c.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let (vertices_count, faces_count) = match counts.as_slice() {
+            [v, f, ..] => (*v, *f),
+            _ => return Err(anyhow!("OFF counts are missing")),
+        };
+
+        let vertices = (0..vertices_count)
+            .map(|_| {
+                let items = lines.next().ok_or_else(|| anyhow!("Not enough OFF vertices"))?;
+                let coords = items
+                    .iter()
+                    .take(3)
+                    .map(|c| c.parse::<f64>().map(S::from_value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match coords.as_slice() {
+                    [x, y, z] => Ok(Vector3::new(*x, *y, *z)),
+                    _ => Err(anyhow!("OFF vertex needs 3 coordinates")),
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let polygons = (0..faces_count)
+            .map(|_| {
+                let items = lines.next().ok_or_else(|| anyhow!("Not enough OFF faces"))?;
+                let count = items
+                    .first()
+                    .ok_or_else(|| anyhow!("Empty OFF face"))?
+                    .parse::<usize>()?;
+                items
+                    .iter()
+                    .skip(1)
+                    .take(count)
+                    .map(|ix| {
+                        let ix = ix.parse::<usize>()?;
+                        vertices
+                            .get(ix)
+                            .copied()
+                            .ok_or_else(|| anyhow!("OFF face vertex {ix} is out of range"))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(import_polygons(self, polygons))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    #[test]
+    fn off_round_trip() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+        let mut buffer = Vec::new();
+        index.write_off(&mut buffer, &[a, b]).unwrap();
+
+        let mut loaded = new_index();
+        let import = loaded.load_off(buffer.as_slice()).unwrap();
+        assert!(import.rejected.is_empty());
+        let mesh = import.mesh_id.make_ref(&loaded);
+        assert_eq!(mesh.all_polygons().len(), 12);
+        assert_eq!(mesh.triangulated().unwrap().len(), 24);
+        assert!((mesh.volume() - 9.0).abs() < 1e-9);
+        assert!(mesh.validate().is_valid());
+    }
+}
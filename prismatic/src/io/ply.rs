@@ -0,0 +1,414 @@
+use std::io::{BufRead, Write};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use math::{Scalar, Vector3};
+
+use crate::indexes::geo_index::{index::GeoIndex, mesh::MeshId};
+
+use super::{collect_polygons, import_polygons, Import};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+impl PlyFormat {
+    fn header_name(&self) -> &'static str {
+        match self {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        }
+    }
+
+    fn write_value<W: Write>(&self, writer: &mut W, ty: PlyType, value: f64) -> anyhow::Result<()> {
+        macro_rules! bytes {
+            ($t: ty) => {
+                match self {
+                    PlyFormat::BinaryBigEndian => (value as $t).to_be_bytes().to_vec(),
+                    _ => (value as $t).to_le_bytes().to_vec(),
+                }
+            };
+        }
+        if *self == PlyFormat::Ascii {
+            write!(writer, "{value}")?;
+            return Ok(());
+        }
+        let bytes = match ty {
+            PlyType::Char => bytes!(i8),
+            PlyType::UChar => bytes!(u8),
+            PlyType::Short => bytes!(i16),
+            PlyType::UShort => bytes!(u16),
+            PlyType::Int => bytes!(i32),
+            PlyType::UInt => bytes!(u32),
+            PlyType::Float => bytes!(f32),
+            PlyType::Double => bytes!(f64),
+        };
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PlyType {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "char" | "int8" => Ok(Self::Char),
+            "uchar" | "uint8" => Ok(Self::UChar),
+            "short" | "int16" => Ok(Self::Short),
+            "ushort" | "uint16" => Ok(Self::UShort),
+            "int" | "int32" => Ok(Self::Int),
+            "uint" | "uint32" => Ok(Self::UInt),
+            "float" | "float32" => Ok(Self::Float),
+            "double" | "float64" => Ok(Self::Double),
+            _ => Err(anyhow!("Unknown PLY property type `{name}`")),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PlyType::Char => "char",
+            PlyType::UChar => "uchar",
+            PlyType::Short => "short",
+            PlyType::UShort => "ushort",
+            PlyType::Int => "int",
+            PlyType::UInt => "uint",
+            PlyType::Float => "float",
+            PlyType::Double => "double",
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            PlyType::Char | PlyType::UChar => 1,
+            PlyType::Short | PlyType::UShort => 2,
+            PlyType::Int | PlyType::UInt | PlyType::Float => 4,
+            PlyType::Double => 8,
+        }
+    }
+}
+
+enum PlyProperty {
+    Scalar(String, PlyType),
+    List(String, PlyType, PlyType),
+}
+
+impl PlyProperty {
+    fn name(&self) -> &str {
+        match self {
+            PlyProperty::Scalar(name, _) | PlyProperty::List(name, _, _) => name,
+        }
+    }
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+/// Reads values of PLY body one by one.
+struct PlyBody {
+    format: PlyFormat,
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl PlyBody {
+    /// Reads count or index, which must be non-negative integer.
+    fn next_index(&mut self, ty: PlyType) -> anyhow::Result<usize> {
+        let value = self.next(ty)?;
+        if value < 0.0 || value.fract() != 0.0 {
+            return Err(anyhow!("PLY index {value} is not a non-negative integer"));
+        }
+        Ok(value as usize)
+    }
+
+    fn next(&mut self, ty: PlyType) -> anyhow::Result<f64> {
+        match self.format {
+            PlyFormat::Ascii => {
+                while self.data.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+                    self.pos += 1;
+                }
+                let start = self.pos;
+                while self.data.get(self.pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+                    self.pos += 1;
+                }
+                let token = std::str::from_utf8(&self.data[start..self.pos])?;
+                if token.is_empty() {
+                    return Err(anyhow!("Unexpected end of PLY data"));
+                }
+                Ok(token.parse::<f64>()?)
+            }
+            PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => {
+                let bytes = self
+                    .data
+                    .get(self.pos..self.pos + ty.size())
+                    .ok_or_else(|| anyhow!("Unexpected end of PLY data"))?;
+                self.pos += ty.size();
+                macro_rules! value {
+                    ($t: ty) => {{
+                        let bytes = bytes.try_into()?;
+                        if self.format == PlyFormat::BinaryBigEndian {
+                            <$t>::from_be_bytes(bytes) as f64
+                        } else {
+                            <$t>::from_le_bytes(bytes) as f64
+                        }
+                    }};
+                }
+                Ok(match ty {
+                    PlyType::Char => value!(i8),
+                    PlyType::UChar => value!(u8),
+                    PlyType::Short => value!(i16),
+                    PlyType::UShort => value!(u16),
+                    PlyType::Int => value!(i32),
+                    PlyType::UInt => value!(u32),
+                    PlyType::Float => value!(f32),
+                    PlyType::Double => value!(f64),
+                })
+            }
+        }
+    }
+}
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Writes meshes as single PLY file.
+    /// With `face_attributes`, each face also gets `mesh_id`, `face_id` and `split` properties.
+    /// `split` is set for faces, which were created by splitting other face.
+    pub fn write_ply<W: Write>(
+        &self,
+        writer: &mut W,
+        meshes: &[MeshId],
+        format: PlyFormat,
+        face_attributes: bool,
+    ) -> anyhow::Result<()> {
+        let (points, polygons) = collect_polygons(self, meshes)?;
+        let max_len = polygons.iter().map(|p| p.vertices.len()).max().unwrap_or_default();
+        let count_type = if max_len <= u8::MAX as usize {
+            PlyType::UChar
+        } else {
+            PlyType::UInt
+        };
+
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format {} 1.0", format.header_name())?;
+        writeln!(writer, "element vertex {}", points.len())?;
+        for axis in ["x", "y", "z"] {
+            writeln!(writer, "property double {axis}")?;
+        }
+        writeln!(writer, "element face {}", polygons.len())?;
+        writeln!(writer, "property list {} int vertex_indices", count_type.name())?;
+        if face_attributes {
+            writeln!(writer, "property int mesh_id")?;
+            writeln!(writer, "property int face_id")?;
+            writeln!(writer, "property uchar split")?;
+        }
+        writeln!(writer, "end_header")?;
+
+        let separator = |writer: &mut W, last: bool| -> anyhow::Result<()> {
+            if format == PlyFormat::Ascii {
+                write!(writer, "{}", if last { "\n" } else { " " })?;
+            }
+            Ok(())
+        };
+
+        for v in points {
+            let coords = [v.x, v.y, v.z].map(|c| c.to_f64().unwrap_or_default());
+            for (ix, c) in coords.into_iter().enumerate() {
+                format.write_value(writer, PlyType::Double, c)?;
+                separator(writer, ix == 2)?;
+            }
+        }
+
+        for polygon in polygons {
+            let mut values = vec![(count_type, polygon.vertices.len() as f64)];
+            values.extend(polygon.vertices.iter().map(|ix| (PlyType::Int, *ix as f64)));
+            if face_attributes {
+                let split = self.find_splitted_face_parent(polygon.face_id).is_some();
+                values.push((PlyType::Int, polygon.mesh_id.0 as f64));
+                values.push((PlyType::Int, polygon.face_id.0 as f64));
+                values.push((PlyType::UChar, if split { 1.0 } else { 0.0 }));
+            }
+            let last = values.len() - 1;
+            for (ix, (ty, value)) in values.into_iter().enumerate() {
+                format.write_value(writer, ty, value)?;
+                separator(writer, ix == last)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads faces of ASCII or binary PLY into new mesh. Properties other than vertex coordinates
+    /// and face vertex indices are ignored.
+    /// Faces, rejected by index, are skipped and reported in result.
+    pub fn load_ply<R: BufRead>(&mut self, mut reader: R) -> anyhow::Result<Import<S>> {
+        let mut line = String::new();
+        let mut format = None;
+        let mut elements: Vec<PlyElement> = Vec::new();
+
+        reader.read_line(&mut line)?;
+        if line.trim() != "ply" {
+            return Err(anyhow!("Not a PLY file"));
+        }
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(anyhow!("PLY header is not finished"));
+            }
+            let items = line.split_whitespace().collect_vec();
+            match items.as_slice() {
+                ["format", "ascii", _] => format = Some(PlyFormat::Ascii),
+                ["format", "binary_little_endian", _] => {
+                    format = Some(PlyFormat::BinaryLittleEndian)
+                }
+                ["format", "binary_big_endian", _] => format = Some(PlyFormat::BinaryBigEndian),
+                ["element", name, count] => elements.push(PlyElement {
+                    name: name.to_string(),
+                    count: count.parse()?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", count_ty, item_ty, name] => elements
+                    .last_mut()
+                    .ok_or_else(|| anyhow!("PLY property without element"))?
+                    .properties
+                    .push(PlyProperty::List(
+                        name.to_string(),
+                        PlyType::parse(count_ty)?,
+                        PlyType::parse(item_ty)?,
+                    )),
+                ["property", ty, name] => elements
+                    .last_mut()
+                    .ok_or_else(|| anyhow!("PLY property without element"))?
+                    .properties
+                    .push(PlyProperty::Scalar(name.to_string(), PlyType::parse(ty)?)),
+                ["end_header"] => break,
+                _ => {}
+            }
+        }
+
+        let mut body = PlyBody {
+            format: format.ok_or_else(|| anyhow!("PLY format is not specified"))?,
+            data: Vec::new(),
+            pos: 0,
+        };
+        reader.read_to_end(&mut body.data)?;
+
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for element in elements {
+            for _ in 0..element.count {
+                let mut coords = [S::zero(); 3];
+                for property in &element.properties {
+                    match property {
+                        PlyProperty::Scalar(name, ty) => {
+                            let value = body.next(*ty)?;
+                            if let Some(axis) = ["x", "y", "z"].iter().position(|a| a == name) {
+                                coords[axis] = S::from_value(value);
+                            }
+                        }
+                        PlyProperty::List(_, count_ty, item_ty) => {
+                            let count = body.next_index(*count_ty)?;
+                            if element.name == "face"
+                                && ["vertex_indices", "vertex_index"].contains(&property.name())
+                            {
+                                let items = (0..count)
+                                    .map(|_| body.next_index(*item_ty))
+                                    .collect::<anyhow::Result<Vec<_>>>()?;
+                                faces.push(items);
+                            } else {
+                                for _ in 0..count {
+                                    body.next(*item_ty)?;
+                                }
+                            }
+                        }
+                    }
+                }
+                if element.name == "vertex" {
+                    vertices.push(Vector3::new(coords[0], coords[1], coords[2]));
+                }
+            }
+        }
+
+        let polygons = faces
+            .into_iter()
+            .map(|face| {
+                face.into_iter()
+                    .map(|ix| {
+                        vertices
+                            .get(ix)
+                            .copied()
+                            .ok_or_else(|| anyhow!("PLY face vertex {ix} is out of range"))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(import_polygons(self, polygons))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    use super::PlyFormat;
+
+    #[test]
+    fn ply_round_trip() {
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let mut index = new_index();
+            let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+            let b = cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+            let mut buffer = Vec::new();
+            index.write_ply(&mut buffer, &[a, b], format, true).unwrap();
+
+            let mut loaded = new_index();
+            let import = loaded.load_ply(buffer.as_slice()).unwrap();
+            assert!(import.rejected.is_empty());
+            let mesh = import.mesh_id.make_ref(&loaded);
+            assert_eq!(mesh.all_polygons().len(), 12);
+            assert_eq!(mesh.triangulated().unwrap().len(), 24);
+            assert!((mesh.volume() - 9.0).abs() < 1e-9);
+            assert!(mesh.validate().is_valid());
+        }
+    }
+
+    #[test]
+    fn ply_rejects_bad_indices() {
+        let ply = |face: &str| {
+            format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+                 property float z\nelement face 1\nproperty list uchar int vertex_indices\n\
+                 end_header\n0 0 0\n1 0 0\n0 1 0\n{face}\n"
+            )
+        };
+        let mut index = new_index();
+        assert!(index.load_ply(ply("3 0 1 2").as_bytes()).is_ok());
+        assert!(index.load_ply(ply("3 0 -1 2").as_bytes()).is_err());
+        assert!(index.load_ply(ply("3 0 1.5 2").as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::{Read, Seek, Write};
+
+use math::{Scalar, Vector3};
+
+use crate::indexes::geo_index::{index::GeoIndex, mesh::OrientedTriangle};
+
+use super::{import_polygons, Import};
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Loads ASCII or binary STL into new mesh.
+    /// Triangles, rejected by index, are skipped and reported in result.
+    pub fn load_stl<R: Read + Seek>(&mut self, mut reader: R) -> anyhow::Result<Import<S>> {
+        let stl = stl_io::read_stl(&mut reader)?;
+        let triangles = stl.faces.iter().map(|triangle| {
+            triangle
+                .vertices
+                .iter()
+                .map(|v| {
+                    let v = stl.vertices[*v];
+                    Vector3::new(
+                        S::from_value(v[0]),
+                        S::from_value(v[1]),
+                        S::from_value(v[2]),
+                    )
+                })
+                .collect()
+        });
+
+        Ok(import_polygons(self, triangles))
+    }
+}
+
+pub(crate) fn write_ascii<S: Scalar, W: Write>(
+    writer: &mut W,
+    name: &str,
+    triangles: &[OrientedTriangle<S>],
+) -> anyhow::Result<()> {
+    writeln!(writer, "solid {name}")?;
+    for (normal, vertices) in triangles {
+        writeln!(writer, "  facet normal {} {} {}", normal.x, normal.y, normal.z)?;
+        writeln!(writer, "    outer loop")?;
+        for v in vertices {
+            writeln!(writer, "      vertex {} {} {}", v.x, v.y, v.z)?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {name}")?;
+    Ok(())
+}
+
+pub(crate) fn write_binary<S: Scalar, W: Write>(
+    writer: &mut W,
+    triangles: &[OrientedTriangle<S>],
+) -> anyhow::Result<()> {
+    let to_stl = |v: &Vector3<S>| {
+        stl_io::Vector::new([
+            v.x.to_f32().unwrap_or_default(),
+            v.y.to_f32().unwrap_or_default(),
+            v.z.to_f32().unwrap_or_default(),
+        ])
+    };
+    let triangles = triangles.iter().map(|(normal, vertices)| stl_io::Triangle {
+        normal: to_stl(normal),
+        vertices: vertices.each_ref().map(to_stl),
+    });
+    stl_io::write_stl(writer, triangles)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use math::Vector3;
+
+    use crate::indexes::geo_index::{
+        geo_object::GeoObject,
+        mesh::tests::{cube, new_index},
+    };
+
+    fn round_trip(binary: bool) {
+        let mut index = new_index();
+        let mesh_id = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let mut buffer = Vec::new();
+        mesh_id
+            .make_ref(&index)
+            .write_stl(&mut buffer, binary)
+            .unwrap();
+
+        let mut loaded = new_index();
+        let import = loaded.load_stl(Cursor::new(buffer)).unwrap();
+        assert!(import.rejected.is_empty());
+        let mesh = import.mesh_id.make_ref(&loaded);
+        assert_eq!(mesh.triangulated().unwrap().len(), 12);
+        assert!((mesh.volume() - 8.0).abs() < 1e-6);
+        assert!(mesh.validate().is_valid());
+    }
+
+    #[test]
+    fn ascii_stl_round_trip() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn binary_stl_round_trip() {
+        round_trip(true);
+    }
+
+    #[test]
+    fn stl_facets_look_outside() {
+        let mut index = new_index();
+        let mesh_id = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let mesh = mesh_id.make_ref(&index);
+
+        let mut binary = Vec::new();
+        mesh.write_stl(&mut binary, true).unwrap();
+        // Header, triangle count and 50 bytes per triangle.
+        assert_eq!(binary.len(), 84 + 50 * 12);
+
+        let mut ascii = Vec::new();
+        mesh.write_stl(&mut ascii, false).unwrap();
+        let stl = stl_io::read_stl(&mut Cursor::new(ascii)).unwrap();
+        assert_eq!(stl.faces.len(), 12);
+        for face in stl.faces {
+            let center = face
+                .vertices
+                .iter()
+                .map(|v| stl.vertices[*v])
+                .fold([0.0; 3], |sum, v| {
+                    [sum[0] + v[0], sum[1] + v[1], sum[2] + v[2]]
+                });
+            let n = face.normal;
+            assert!(n[0] * center[0] + n[1] * center[1] + n[2] * center[2] > 0.0);
+        }
+    }
+}
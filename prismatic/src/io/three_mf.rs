@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    io::{Seek, Write},
+};
+
+use math::Scalar;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::indexes::geo_index::{geo_object::GeoObject, index::GeoIndex, mesh::MeshId};
+
+use super::check_meshes;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+ <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+ <Default Extension="model" ContentType="application/vnd.ms-package.3dmanufacturing-3dmodel+xml"/>
+</Types>
+"#;
+
+const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+ <Relationship Target="/3D/3dmodel.model" Id="rel0" Type="http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel"/>
+</Relationships>
+"#;
+
+impl<S: Scalar> GeoIndex<S> {
+    /// Writes meshes as 3MF package, each mesh as separate object.
+    /// Meshes, which have color in `colors` (RGB), get own base material with this color.
+    /// Index does not know anything about units, so coordinates are written as millimeters.
+    pub fn write_3mf<W: Write + Seek>(
+        &self,
+        writer: W,
+        meshes: &[MeshId],
+        colors: &HashMap<MeshId, [u8; 3]>,
+    ) -> anyhow::Result<()> {
+        let model = self.model_3mf(meshes, colors)?;
+
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file("[Content_Types].xml", options)?;
+        zip.write_all(CONTENT_TYPES.as_bytes())?;
+        zip.start_file("_rels/.rels", options)?;
+        zip.write_all(RELS.as_bytes())?;
+        zip.start_file("3D/3dmodel.model", options)?;
+        zip.write_all(model.as_bytes())?;
+        zip.finish()?;
+
+        Ok(())
+    }
+
+    fn model_3mf(
+        &self,
+        meshes: &[MeshId],
+        colors: &HashMap<MeshId, [u8; 3]>,
+    ) -> anyhow::Result<String> {
+        check_meshes(self, meshes)?;
+        let colored = meshes
+            .iter()
+            .filter_map(|mesh_id| colors.get(mesh_id).map(|color| (*mesh_id, *color)))
+            .collect::<Vec<_>>();
+        // Base materials take resource id 1, objects are numbered after it.
+        let materials_id = 1;
+        let mut resources = Vec::new();
+        if !colored.is_empty() {
+            let bases = colored
+                .iter()
+                .map(|(mesh_id, [r, g, b])| {
+                    format!(
+                        "  <base name=\"mesh_{}\" displaycolor=\"#{r:02X}{g:02X}{b:02X}FF\"/>",
+                        mesh_id.0
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            resources.push(format!(
+                " <basematerials id=\"{materials_id}\">\n{bases}\n </basematerials>"
+            ));
+        }
+
+        let mut items = Vec::new();
+        for (ix, mesh_id) in meshes.iter().enumerate() {
+            let object_id = materials_id + 1 + ix;
+            let (points, triangles) = mesh_id.make_ref(self).indexed_triangles()?;
+            let material = colored
+                .iter()
+                .position(|(colored_id, _)| colored_id == mesh_id)
+                .map(|pindex| format!(" pid=\"{materials_id}\" pindex=\"{pindex}\""))
+                .unwrap_or_default();
+            let vertices = points
+                .iter()
+                .map(|v| format!("    <vertex x=\"{}\" y=\"{}\" z=\"{}\"/>", v.x, v.y, v.z))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let triangles = triangles
+                .iter()
+                .map(|[a, b, c]| format!("    <triangle v1=\"{a}\" v2=\"{b}\" v3=\"{c}\"/>"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            resources.push(format!(
+                " <object id=\"{object_id}\" name=\"mesh_{}\" type=\"model\"{material}>\n  <mesh>\n   <vertices>\n{vertices}\n   </vertices>\n   <triangles>\n{triangles}\n   </triangles>\n  </mesh>\n </object>",
+                mesh_id.0
+            ));
+            items.push(format!(" <item objectid=\"{object_id}\"/>"));
+        }
+
+        Ok(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<model unit=\"millimeter\" xml:lang=\"en-US\" xmlns=\"http://schemas.microsoft.com/3dmanufacturing/core/2015/02\">\n<resources>\n{}\n</resources>\n<build>\n{}\n</build>\n</model>\n",
+            resources.join("\n"),
+            items.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{Cursor, Read},
+    };
+
+    use math::Vector3;
+    use zip::ZipArchive;
+
+    use crate::indexes::geo_index::mesh::tests::{cube, new_index};
+
+    #[test]
+    fn three_mf_package() {
+        let mut index = new_index();
+        let a = cube(&mut index, Vector3::new(0.0, 0.0, 0.0), 2.0);
+        let b = cube(&mut index, Vector3::new(4.0, 0.0, 0.0), 1.0);
+        let colors = HashMap::from([(b, [255, 0, 0])]);
+        let mut buffer = Cursor::new(Vec::new());
+        index.write_3mf(&mut buffer, &[a, b], &colors).unwrap();
+
+        let mut zip = ZipArchive::new(buffer).unwrap();
+        let mut names = zip.file_names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            ["3D/3dmodel.model", "[Content_Types].xml", "_rels/.rels"]
+        );
+
+        let mut model = String::new();
+        zip.by_name("3D/3dmodel.model")
+            .unwrap()
+            .read_to_string(&mut model)
+            .unwrap();
+        assert_eq!(model.matches("<object ").count(), 2);
+        assert_eq!(model.matches("<item ").count(), 2);
+        assert_eq!(model.matches("<triangle ").count(), 24);
+        assert_eq!(model.matches("<vertex ").count(), 16);
+        assert_eq!(model.matches("<base ").count(), 1);
+    }
+}
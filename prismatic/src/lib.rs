@@ -1,6 +1,8 @@
 pub mod constants;
+pub mod csg;
 pub mod geometry;
 pub mod indexes;
+pub mod io;
 pub mod linear;
 pub mod planar;
 pub mod point;
@@ -5,6 +5,7 @@ use math::{Scalar, Vector3};
 use crate::constants::STABILITY_ROUNDING;
 
 #[derive(Clone, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Plane<S: Scalar> {
     normal: Vector3<S>,
     d: S,